@@ -1,2 +1,4 @@
+pub mod console;
 pub mod plic;
+pub mod uart;
 pub mod virtio;
@@ -0,0 +1,217 @@
+//! Console Input
+//!
+//! Bytes typed on the console are queued until a thread reads them. There are
+//! two modes:
+//!
+//! - Cooked (default): input is echoed and edited a line at a time. Backspace
+//!   erases a byte, Ctrl-U erases the line, and Enter or Ctrl-D makes the line
+//!   readable. Ctrl-D on an empty line makes the next read return 0 (EOF).
+//!   Ctrl-C discards the line and sends SIGINT to the foreground process.
+//! - Raw: bytes are readable as soon as they arrive, without echo.
+//!
+//! [`input`] is fed by the UART interrupt handler, and [`read`] blocks until
+//! there is something to read.
+//!
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::sbi::{self, interrupt};
+use crate::sync::{Condvar, Intr, Lazy, Mutex};
+use crate::userproc::{self, signal};
+use crate::{OsError, Result};
+
+/// Capacity of the input queue. Input beyond it is dropped.
+pub const INPUT_SIZE: usize = 1024;
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const CTRL_U: u8 = 0x15;
+
+/// How input is processed. Values are the same as `CONS_*` in `user/lib/user.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Cooked = 0,
+    Raw = 1,
+}
+
+struct Console {
+    input: Mutex<Input, Intr>,
+    /// Notified when the queue becomes non-empty, or a signal is sent.
+    readable: Condvar,
+}
+
+struct Input {
+    mode: Mode,
+    /// Bytes ready to read. `None` marks an EOF typed with Ctrl-D.
+    queue: VecDeque<Option<u8>>,
+    /// The line being edited in cooked mode.
+    line: Vec<u8>,
+}
+
+static CONSOLE: Lazy<Console> = Lazy::new(|| Console {
+    input: Mutex::new(Input {
+        mode: Mode::Cooked,
+        queue: VecDeque::with_capacity(INPUT_SIZE),
+        line: Vec::new(),
+    }),
+    readable: Condvar::new(),
+});
+
+impl Input {
+    /// Handles a byte in cooked mode. Returns whether a line is finished.
+    fn cook(&mut self, byte: u8) -> bool {
+        match byte {
+            b'\r' | b'\n' => {
+                self.line.push(b'\n');
+                echo(b"\n");
+                self.commit();
+                true
+            }
+            BACKSPACE | DELETE => {
+                if self.line.pop().is_some() {
+                    echo(b"\x08 \x08");
+                }
+                false
+            }
+            CTRL_U => {
+                self.line.drain(..).for_each(|_| echo(b"\x08 \x08"));
+                false
+            }
+            CTRL_D if self.line.is_empty() => {
+                if self.queue.len() < INPUT_SIZE {
+                    self.queue.push_back(None);
+                }
+                true
+            }
+            CTRL_D => {
+                self.commit();
+                true
+            }
+            byte => {
+                // Leave room for the line break.
+                if self.queue.len() + self.line.len() + 1 < INPUT_SIZE {
+                    self.line.push(byte);
+                    echo(&[byte]);
+                }
+                false
+            }
+        }
+    }
+
+    /// Moves the edited line to the queue.
+    fn commit(&mut self) {
+        let line = self.line.drain(..).map(Some);
+        self.queue.extend(line);
+    }
+}
+
+fn echo(bytes: &[u8]) {
+    bytes.iter().for_each(|b| sbi::console_putchar(*b as usize));
+}
+
+/// Switches the input mode, and returns the previous one. A line being
+/// edited becomes readable when switching to raw mode.
+pub fn set_mode(mode: Mode) -> Mode {
+    let old = interrupt::set(false);
+
+    let (prev, ready) = {
+        let mut input = CONSOLE.input.lock();
+        let ready = mode == Mode::Raw && !input.line.is_empty();
+        if ready {
+            input.commit();
+        }
+        (core::mem::replace(&mut input.mode, mode), ready)
+    };
+    if ready {
+        CONSOLE.readable.notify_all();
+    }
+
+    interrupt::set(old);
+    prev
+}
+
+/// Handles a byte typed on the console.
+pub fn input(byte: u8) {
+    // Readers are woken after releasing the lock, since one of them may be
+    // scheduled at once. Interrupts stay off so no reader can slip in between.
+    let old = interrupt::set(false);
+
+    let (ready, sigint) = {
+        let mut input = CONSOLE.input.lock();
+        match input.mode {
+            Mode::Cooked if byte == CTRL_C => {
+                input.line.clear();
+                echo(b"^C\n");
+                (false, true)
+            }
+            Mode::Cooked => (input.cook(byte), false),
+            Mode::Raw if input.queue.len() < INPUT_SIZE => {
+                input.queue.push_back(Some(byte));
+                (true, false)
+            }
+            Mode::Raw => (false, false),
+        }
+    };
+    if sigint {
+        // Wakes up readers by itself.
+        let _ = signal::kill(userproc::foreground(), signal::Signal::Int);
+    } else if ready {
+        CONSOLE.readable.notify_all();
+    }
+
+    interrupt::set(old);
+}
+
+/// Wakes up readers, so that they can check for signals.
+pub fn interrupt() {
+    let old = interrupt::set(false);
+    CONSOLE.readable.notify_all();
+    interrupt::set(old);
+}
+
+/// Reads input into `buf`, blocking until some is available. In cooked mode
+/// a read doesn't go past the end of a line. Returns 0 at EOF.
+///
+/// ## Errors
+/// - [`OsError::Interrupted`]: a signal arrived while waiting.
+pub fn read(buf: &mut [u8]) -> Result<usize> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+
+    let mut input = CONSOLE.input.lock();
+    while input.queue.is_empty() {
+        if signal::pending() {
+            return Err(OsError::Interrupted);
+        }
+        if CONSOLE.readable.wait_interruptible(&mut input) {
+            return Err(OsError::Interrupted);
+        }
+    }
+
+    let mut len = 0;
+    while len < buf.len() {
+        match input.queue.front().copied() {
+            Some(Some(byte)) => {
+                buf[len] = byte;
+                len += 1;
+                input.queue.pop_front();
+                if byte == b'\n' && input.mode == Mode::Cooked {
+                    break;
+                }
+            }
+            // Consume the EOF only if nothing is read before it.
+            Some(None) => {
+                if len == 0 {
+                    input.queue.pop_front();
+                }
+                break;
+            }
+            None => break,
+        }
+    }
+    Ok(len)
+}
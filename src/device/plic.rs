@@ -12,6 +12,8 @@ use crate::sync::OnceCell;
 
 /// Hard-coded Virtio device 0 interrupt identifier (ID).
 pub const VIRTIO0_ID: usize = 1;
+/// Hard-coded UART 0 interrupt identifier (ID).
+pub const UART0_ID: usize = 10;
 
 // Hart ID.
 static HART_ID: OnceCell<usize> = OnceCell::new();
@@ -29,6 +31,10 @@ pub fn init(hart_id: usize) {
 
         // Enable this hart to receive interrupts from Virtio device 0.
         set_enable(VIRTIO0_ID);
+
+        // Same for UART 0, which interrupts when input arrives.
+        write_priority(UART0_ID, 1);
+        set_enable(UART0_ID);
     }
 }
 
@@ -0,0 +1,48 @@
+//! UART Input Support
+//!
+//! QEMU emulates a 16550 UART, which is also where `sbi::console_putchar`
+//! writes to. We leave output to SBI, and only take over input: the UART
+//! interrupts when bytes arrive, and [`handle_interrupt`] passes them to
+//! [`console`](super::console).
+//!
+//! For more information, see <http://byterunner.com/16550.html>.
+//!
+
+use crate::device::console;
+use crate::mem::UART_BASE;
+
+// A subset of 16550 registers.
+const RHR: *const u8 = UART_BASE as _; // Receive holding register.
+const IER: *mut u8 = (UART_BASE + 1) as _; // Interrupt enable register.
+const FCR: *mut u8 = (UART_BASE + 2) as _; // FIFO control register.
+const LSR: *const u8 = (UART_BASE + 5) as _; // Line status register.
+
+const IER_RX_ENABLE: u8 = 1 << 0;
+const FCR_FIFO_ENABLE: u8 = 1 << 0;
+const FCR_FIFO_CLEAR: u8 = 3 << 1;
+const LSR_RX_READY: u8 = 1 << 0;
+
+/// Enables receive interrupts. The line is already set up by the firmware.
+pub fn init() {
+    unsafe {
+        FCR.write_volatile(FCR_FIFO_ENABLE | FCR_FIFO_CLEAR);
+        IER.write_volatile(IER_RX_ENABLE);
+    }
+}
+
+/// Reads a received byte, if there is one.
+fn getc() -> Option<u8> {
+    unsafe {
+        match LSR.read_volatile() & LSR_RX_READY {
+            0 => None,
+            _ => Some(RHR.read_volatile()),
+        }
+    }
+}
+
+/// Drains received bytes into the console.
+pub fn handle_interrupt() {
+    while let Some(byte) = getc() {
+        console::input(byte);
+    }
+}
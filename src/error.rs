@@ -18,4 +18,13 @@ pub enum OsError {
     ArgumentTooLong = -11,
     InvalidFileMode = -12,
     FileNotOpened = -13,
+    NotADirectory = -14,
+    IsADirectory = -15,
+    DirectoryNotEmpty = -16,
+    FileTooLarge = -17,
+    BrokenPipe = -18,
+    Interrupted = -19,
+    NoSuchProcess = -20,
+    Panicked = -21,
+    Detached = -22,
 }
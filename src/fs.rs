@@ -1,14 +1,21 @@
 //! File System Interface
 //!
 
+pub mod devfs;
 pub mod disk;
 pub mod inmem;
+pub mod pipe;
+pub mod procfs;
+pub mod tmpfs;
 
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use crate::io::{Read, Seek, Write};
 use crate::sync::Mutex;
-use crate::Result;
+use crate::{OsError, Result};
 
 /* -------------------------------------------------------------------------- */
 /*                                 File System                                */
@@ -58,6 +65,35 @@ pub trait Vnode: Sync + Send {
     fn len(&self) -> usize;
     fn resize(&self, size: usize) -> Result<()>;
     fn close(&self);
+
+    /// What kind of object this inode is. Most inodes are regular files.
+    fn kind(&self) -> FileType {
+        FileType::File
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                  Metadata                                  */
+/* -------------------------------------------------------------------------- */
+
+/// Kind of an object in a file system.
+///
+/// Values are the same as `T_*` in `user/lib/fstat.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Dir = 1,
+    File = 2,
+    Device = 3,
+    Pipe = 4,
+}
+
+/// Metadata of a file system object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stat {
+    pub inum: usize,
+    /// Length in bytes. Zero for directories.
+    pub len: usize,
+    pub kind: FileType,
 }
 
 /* -------------------------------------------------------------------------- */
@@ -82,6 +118,14 @@ impl File {
     pub fn inum(&self) -> usize {
         self.vnode.inum()
     }
+
+    pub fn stat(&self) -> Stat {
+        Stat {
+            inum: self.vnode.inum(),
+            len: self.vnode.len(),
+            kind: self.vnode.kind(),
+        }
+    }
 }
 
 impl Read for File {
@@ -136,3 +180,138 @@ impl Drop for File {
         }
     }
 }
+
+/* -------------------------------------------------------------------------- */
+/*                                 Mount Point                                */
+/* -------------------------------------------------------------------------- */
+
+/// Where [`tmpfs::TMPFS`] is mounted. Paths not under any other
+/// mount point go to [`disk::DISKFS`].
+pub const TMPFS_ROOT: &str = "/tmp";
+/// Where [`procfs::PROCFS`] is mounted.
+pub const PROCFS_ROOT: &str = "/proc";
+/// Where [`devfs::DEVFS`] is mounted.
+pub const DEVFS_ROOT: &str = "/dev";
+
+/// Mount points, which appear as directories in the root dir.
+const MOUNT_POINTS: [&str; 3] = [TMPFS_ROOT, PROCFS_ROOT, DEVFS_ROOT];
+
+/// A path resolved to the file system that holds it.
+enum Mount<'a> {
+    Disk(&'a str),
+    Tmp(&'a str),
+    Proc(&'a str),
+    Dev(&'a str),
+}
+
+impl<'a> Mount<'a> {
+    fn of(path: &'a str) -> Self {
+        if let Some(rest) = strip_root(path, TMPFS_ROOT) {
+            Mount::Tmp(rest)
+        } else if let Some(rest) = strip_root(path, PROCFS_ROOT) {
+            Mount::Proc(rest)
+        } else if let Some(rest) = strip_root(path, DEVFS_ROOT) {
+            Mount::Dev(rest)
+        } else {
+            Mount::Disk(path)
+        }
+    }
+}
+
+/// Splits a path into its components. Empty components and `.` are skipped,
+/// so `/a//b/./c` and `a/b/c` are the same path.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|c| !c.is_empty() && *c != ".")
+}
+
+/// Resolves `path` against the directory `cwd`, into an absolute path
+/// without `.` or `..`.
+pub fn absolute(cwd: &str, path: &str) -> String {
+    let base = if path.starts_with('/') { "" } else { cwd };
+    let mut names: Vec<&str> = Vec::new();
+    for name in components(base).chain(components(path)) {
+        match name {
+            ".." => {
+                names.pop();
+            }
+            name => names.push(name),
+        }
+    }
+    format!("/{}", names.join("/"))
+}
+
+/// Resolves the name of a file on disk, where the root is the only dir.
+fn disk_name(path: &str) -> Result<disk::Path> {
+    let names: Vec<&str> = components(path).collect();
+    match names[..] {
+        [name] => Ok(name.into()),
+        [] => Err(OsError::IsADirectory),
+        _ => Err(OsError::NoSuchFile),
+    }
+}
+
+/// Strips `root` from `path` if `path` lies under it.
+fn strip_root<'a>(path: &'a str, root: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(root)?;
+    (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+/// Opens the file at `path`, on whichever file system it is mounted.
+pub fn open(path: &str) -> Result<File> {
+    match Mount::of(path) {
+        Mount::Disk(path) => disk::DISKFS.open(disk_name(path)?),
+        Mount::Tmp(path) => tmpfs::TMPFS.open(path.into()),
+        Mount::Proc(path) => procfs::PROCFS.open(path.into()),
+        Mount::Dev(path) => devfs::DEVFS.open(path.into()),
+    }
+}
+
+/// Creates (or truncates) the file at `path`.
+pub fn create(path: &str) -> Result<File> {
+    match Mount::of(path) {
+        Mount::Disk(path) => disk::DISKFS.create(disk_name(path)?),
+        Mount::Tmp(path) => tmpfs::TMPFS.create(path.into()),
+        Mount::Proc(path) => procfs::PROCFS.create(path.into()),
+        Mount::Dev(path) => devfs::DEVFS.create(path.into()),
+    }
+}
+
+/// Removes the file at `path`.
+pub fn remove(path: &str) -> Result<()> {
+    match Mount::of(path) {
+        Mount::Disk(path) => disk::DISKFS.remove(disk_name(path)?),
+        Mount::Tmp(path) => tmpfs::TMPFS.remove(path.into()),
+        Mount::Proc(path) => procfs::PROCFS.remove(path.into()),
+        Mount::Dev(path) => devfs::DEVFS.remove(path.into()),
+    }
+}
+
+/// Lists entries of the directory at `path`.
+pub fn read_dir(path: &str) -> Result<Vec<(String, FileType)>> {
+    match Mount::of(path) {
+        Mount::Disk(path) if components(path).next().is_some() => {
+            disk::DISKFS.read_dir(disk_name(path)?)
+        }
+        Mount::Disk(_) => {
+            let mut entries = disk::DISKFS.read_dir("".into())?;
+            entries.extend(
+                MOUNT_POINTS
+                    .iter()
+                    .map(|root| (root[1..].to_string(), FileType::Dir)),
+            );
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Ok(entries)
+        }
+        Mount::Tmp(path) => tmpfs::TMPFS.read_dir(path.into()),
+        Mount::Proc(path) => procfs::PROCFS.read_dir(path.into()),
+        Mount::Dev(path) => devfs::DEVFS.read_dir(path.into()),
+    }
+}
+
+/// Creates a directory at `path`. Only tmpfs has a directory tree.
+pub fn mkdir(path: &str) -> Result<()> {
+    match Mount::of(path) {
+        Mount::Tmp(path) => tmpfs::TMPFS.mkdir(path.into()),
+        _ => Err(OsError::InvalidFileMode),
+    }
+}
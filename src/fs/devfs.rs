@@ -0,0 +1,287 @@
+//! Device file system.
+//!
+//! A flat file system whose files are devices. Reads and writes on them
+//! go straight to the device through the ordinary [`Vnode`] interface:
+//!
+//! ```text
+//! /console    the console, reads block until a line is typed
+//! /null       discards writes, reads nothing
+//! /zero       discards writes, reads zeros
+//! /random     ARC4 pseudo-random bytes, same as `user/lib/random.c`
+//! /disk       raw sectors of the virtio block device
+//! ```
+//!
+//! Devices are never created or removed, so every [`File`] opened on the
+//! same name shares one inode.
+//!
+//! `/disk` lies underneath the mounted disk file system, so it's read-only,
+//! and user processes can't open it.
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::min;
+
+use super::disk::Path;
+use super::inmem::alloc_inum;
+use super::{components, File, FileSys, FileType, Vnode};
+use crate::device::console;
+use crate::device::virtio::{Virtio, SECTOR_SIZE};
+use crate::sbi;
+use crate::sync::{Intr, Lazy, Mutex};
+use crate::thread;
+use crate::{OsError, Result};
+
+/// Global device filesys, mounted at [`super::DEVFS_ROOT`].
+pub static DEVFS: Lazy<DevFs> = Lazy::new(|| DevFs::mount(()).expect("Dev fs mounting failed"));
+
+/// Device file system.
+///
+/// # See
+/// [`crate::fs::devfs::DEVFS`].
+pub struct DevFs {
+    devices: BTreeMap<&'static str, Arc<dyn Vnode>>,
+}
+
+impl DevFs {
+    /// Lists all devices, sorted by name.
+    pub fn read_dir(&self, path: Path) -> Result<Vec<(String, FileType)>> {
+        match components(&path).next() {
+            None => Ok(self
+                .devices
+                .keys()
+                .map(|name| (name.to_string(), FileType::Device))
+                .collect()),
+            Some(name) if self.devices.contains_key(name) => Err(OsError::NotADirectory),
+            Some(_) => Err(OsError::NoSuchFile),
+        }
+    }
+}
+
+impl FileSys for DevFs {
+    type Device = ();
+    type Path = Path;
+
+    fn mount(_device: Self::Device) -> Result<Self> {
+        let mut devices: BTreeMap<&'static str, Arc<dyn Vnode>> = BTreeMap::new();
+        devices.insert("console", Arc::new(Device::new(Console)));
+        devices.insert("null", Arc::new(Device::new(Null)));
+        devices.insert("zero", Arc::new(Device::new(Zero)));
+        devices.insert(
+            "random",
+            Arc::new(Device::new(Random(Mutex::new(Arc4::new(
+                sbi::timer::clock() as u32,
+            ))))),
+        );
+        devices.insert("disk", Arc::new(Device::new(Disk)));
+        Ok(Self { devices })
+    }
+
+    fn unmount(&self) {}
+
+    fn open(&self, id: Self::Path) -> Result<File> {
+        let names: Vec<&str> = components(&id).collect();
+        match names[..] {
+            ["disk"] if thread::current().userproc.is_some() => Err(OsError::InvalidFileMode),
+            [name] => self
+                .devices
+                .get(name)
+                .map(|dev| File::new(dev.clone()))
+                .ok_or(OsError::NoSuchFile),
+            [] => Err(OsError::IsADirectory),
+            _ => Err(OsError::NoSuchFile),
+        }
+    }
+
+    fn close(&self, _file: File) {}
+
+    fn create(&self, _id: Self::Path) -> Result<File> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn remove(&self, _id: Self::Path) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                   Devices                                  */
+/* -------------------------------------------------------------------------- */
+
+/// Byte-level access to a device. Offsets only matter to seekable
+/// devices, streams ignore them.
+trait CharDev: Sync + Send {
+    fn read_at(&self, buf: &mut [u8], off: usize) -> Result<usize>;
+    fn write_at(&self, buf: &[u8], off: usize) -> Result<usize>;
+
+    /// Size of a seekable device. Streams have no size.
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+/// Adapts a [`CharDev`] to a [`Vnode`].
+struct Device<D> {
+    inum: usize,
+    dev: D,
+}
+
+impl<D: CharDev> Device<D> {
+    fn new(dev: D) -> Self {
+        Self {
+            inum: alloc_inum(),
+            dev,
+        }
+    }
+}
+
+impl<D: CharDev> Vnode for Device<D> {
+    fn read_at(&self, buf: &mut [u8], off: usize) -> Result<usize> {
+        self.dev.read_at(buf, off)
+    }
+
+    fn write_at(&self, buf: &[u8], off: usize) -> Result<usize> {
+        self.dev.write_at(buf, off)
+    }
+
+    fn deny_write(&self) {}
+    fn allow_write(&self) {}
+
+    fn inum(&self) -> usize {
+        self.inum
+    }
+
+    fn len(&self) -> usize {
+        self.dev.len()
+    }
+
+    fn resize(&self, _size: usize) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn close(&self) {}
+
+    fn kind(&self) -> FileType {
+        FileType::Device
+    }
+}
+
+/// The console. Output goes through SBI, input comes from the UART.
+struct Console;
+
+impl CharDev for Console {
+    /// Reads typed input, see [`crate::device::console`].
+    fn read_at(&self, buf: &mut [u8], _off: usize) -> Result<usize> {
+        console::read(buf)
+    }
+
+    fn write_at(&self, buf: &[u8], _off: usize) -> Result<usize> {
+        let _lock = sbi::console::stdout().lock();
+        buf.iter().for_each(|b| sbi::console_putchar(*b as usize));
+        Ok(buf.len())
+    }
+}
+
+struct Null;
+
+impl CharDev for Null {
+    fn read_at(&self, _buf: &mut [u8], _off: usize) -> Result<usize> {
+        Ok(0)
+    }
+
+    fn write_at(&self, buf: &[u8], _off: usize) -> Result<usize> {
+        Ok(buf.len())
+    }
+}
+
+struct Zero;
+
+impl CharDev for Zero {
+    fn read_at(&self, buf: &mut [u8], _off: usize) -> Result<usize> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, buf: &[u8], _off: usize) -> Result<usize> {
+        Ok(buf.len())
+    }
+}
+
+/// RC4 keystream, used as a non-cryptographic PRNG.
+struct Arc4 {
+    s: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Arc4 {
+    fn new(seed: u32) -> Self {
+        let seed = seed.to_le_bytes();
+        let mut s = [0u8; 256];
+        s.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(s[i]).wrapping_add(seed[i % seed.len()]);
+            s.swap(i, j as usize);
+        }
+
+        Self { s, i: 0, j: 0 }
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            self.i = self.i.wrapping_add(1);
+            self.j = self.j.wrapping_add(self.s[self.i as usize]);
+            self.s.swap(self.i as usize, self.j as usize);
+
+            let k = self.s[self.i as usize].wrapping_add(self.s[self.j as usize]);
+            *byte = self.s[k as usize];
+        }
+    }
+}
+
+/// Seeded with the clock reading when devfs is mounted.
+struct Random(Mutex<Arc4, Intr>);
+
+impl CharDev for Random {
+    fn read_at(&self, buf: &mut [u8], _off: usize) -> Result<usize> {
+        self.0.lock().fill(buf);
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, buf: &[u8], _off: usize) -> Result<usize> {
+        Ok(buf.len())
+    }
+}
+
+/// The virtio block device, as a read-only array of bytes.
+struct Disk;
+
+impl CharDev for Disk {
+    fn read_at(&self, buf: &mut [u8], off: usize) -> Result<usize> {
+        let end = min(off.saturating_add(buf.len()), self.len());
+        let mut pos = off;
+        let mut sector = [0u8; SECTOR_SIZE];
+
+        while pos < end {
+            let (idx, ofs) = (pos / SECTOR_SIZE, pos % SECTOR_SIZE);
+            let len = min(SECTOR_SIZE - ofs, end - pos);
+            Virtio::read_sector(idx as u64, &mut sector);
+            buf[pos - off..pos - off + len].copy_from_slice(&sector[ofs..ofs + len]);
+            pos += len;
+        }
+
+        Ok(pos.saturating_sub(off))
+    }
+
+    fn write_at(&self, _buf: &[u8], _off: usize) -> Result<usize> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn len(&self) -> usize {
+        Virtio::get().lock().capacity() as usize * SECTOR_SIZE
+    }
+}
@@ -12,13 +12,15 @@ pub use self::path::Path;
 pub use self::swap::Swap;
 
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
 
 use self::dir::RootDir;
 use self::free_map::FreeMap;
 use self::inode::Inode;
 
-use super::{File, FileSys, Vnode};
+use super::{File, FileSys, FileType, Vnode};
 use crate::device::virtio::{Virtio, SECTOR_SIZE};
 use crate::sync::{Lazy, Mutex};
 use crate::{OsError, Result};
@@ -198,6 +200,29 @@ impl FileSys for DiskFs {
     }
 }
 
+impl DiskFs {
+    /// Lists files in the root dir, the only dir on disk.
+    pub fn read_dir(&self, path: Path) -> Result<Vec<(String, FileType)>> {
+        if !path.is_empty() {
+            return match self.root_dir.lock().exists(&path) {
+                true => Err(OsError::NotADirectory),
+                false => Err(OsError::NoSuchFile),
+            };
+        }
+
+        let names = self.root_dir.lock().names()?;
+        Ok(names
+            .into_iter()
+            .map(|name| (name, FileType::File))
+            .collect())
+    }
+
+    /// The number of allocated sectors, and the number of all sectors.
+    pub fn usage(&self) -> (u32, u32) {
+        self.free_map.lock().usage()
+    }
+}
+
 pub(self) fn bytes_to_sectors(bytes: usize) -> u32 {
     ((bytes + SECTOR_SIZE - 1) / SECTOR_SIZE) as u32
 }
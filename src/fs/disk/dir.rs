@@ -1,5 +1,8 @@
 //! Root dir.
 //!
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use super::{Inum, Path};
 use crate::fs::File;
 use crate::io::prelude::*;
@@ -49,6 +52,24 @@ impl RootDir {
         Err(OsError::NoSuchFile)
     }
 
+    /// Names of all files in the root dir.
+    pub fn names(&mut self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        self.0.rewind()?;
+        while let Ok(entry) = self.0.read_into::<DirEntry>() {
+            if !entry.is_valid() {
+                continue;
+            }
+            let name = unsafe {
+                core::ffi::CStr::from_ptr(&entry.name as *const u8)
+                    .to_str()
+                    .or(Err(OsError::CstrFormatErr))?
+            };
+            names.push(name.to_string());
+        }
+        Ok(names)
+    }
+
     /// Check if there is a file with the given name.
     ///
     /// # See
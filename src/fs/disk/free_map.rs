@@ -72,6 +72,12 @@ impl FreeMap {
         self.bits[sector as usize / 8] &= !(1 << sector % 8);
     }
 
+    /// The number of allocated sectors, and the number of all sectors.
+    pub(super) fn usage(&self) -> (u32, u32) {
+        let used = self.bits.iter().map(|b| b.count_ones()).sum();
+        (used, self.size)
+    }
+
     /// Allocate a contiguous array of sectors with `cnt` length.
     pub(super) fn alloc(&mut self, cnt: u32) -> Result<Inum> {
         if cnt == 0 {
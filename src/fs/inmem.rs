@@ -2,6 +2,7 @@ use alloc::boxed::Box;
 use alloc::sync::Weak;
 use alloc::vec::Vec;
 use core::cmp::min;
+use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 
 use crate::{OsError, Result};
 
@@ -19,6 +20,8 @@ use super::*;
 /// copy and wrap a byte buffer into a [`File`]. Calls to
 /// [`FileSys::close()`], [`FileSys::create()`] and
 /// [`FileSys::remove()`] will panic.
+///
+/// For a full in-memory file system, see [`super::tmpfs::TmpFs`].
 pub struct MemFs {
     oft: Mutex<Vec<Weak<Inode>>>,
 }
@@ -38,8 +41,7 @@ impl FileSys for MemFs {
     }
 
     fn open(&self, id: Self::Path) -> Result<File> {
-        let buf = Mutex::new(id);
-        let vnode = Arc::new(Inode { buf });
+        let vnode = Inode::fixed(id);
         let weak = Arc::downgrade(&vnode);
         self.oft.lock().push(weak);
 
@@ -63,14 +65,55 @@ impl FileSys for MemFs {
 /*                                    Inode                                   */
 /* -------------------------------------------------------------------------- */
 
-// TODO: should it be pub or not
-struct Inode {
-    buf: Mutex<Box<[u8]>>,
+/// Longest a growable inode may grow, so that its buffer stays within what
+/// the page allocator hands out at once.
+pub const MAX_LEN: usize = 256 * 1024;
+
+/// An in-memory inode, backed by the kernel heap.
+///
+/// A fixed inode wraps a buffer whose length never changes, as [`MemFs`]
+/// does. A growable one extends itself on writes past its end and supports
+/// [`Vnode::resize()`], up to [`MAX_LEN`] bytes, which is what
+/// [`super::tmpfs::TmpFs`] builds on.
+pub(super) struct Inode {
+    inum: usize,
+    growable: bool,
+    buf: Mutex<Vec<u8>>,
+    deny_write: AtomicUsize,
+}
+
+impl Inode {
+    /// Wraps `buf` into an inode of fixed length.
+    pub(super) fn fixed(buf: Box<[u8]>) -> Arc<Self> {
+        Arc::new(Self::new(buf.into_vec(), false))
+    }
+
+    /// Creates an empty inode that grows on demand.
+    pub(super) fn growable() -> Arc<Self> {
+        Arc::new(Self::new(Vec::new(), true))
+    }
+
+    fn new(buf: Vec<u8>, growable: bool) -> Self {
+        Self {
+            inum: alloc_inum(),
+            growable,
+            buf: Mutex::new(buf),
+            deny_write: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Allocates a unique number for an in-memory inode.
+pub(super) fn alloc_inum() -> usize {
+    /// The next inode's number
+    static INUM: AtomicUsize = AtomicUsize::new(1);
+
+    INUM.fetch_add(1, SeqCst)
 }
 
 impl Vnode for Inode {
     fn inum(&self) -> usize {
-        self.buf.lock().as_ptr() as usize
+        self.inum
     }
 
     fn len(&self) -> usize {
@@ -81,7 +124,13 @@ impl Vnode for Inode {
         // Protect during the whole process.
         let lock = self.buf.lock();
         if off >= lock.len() {
-            return Err(OsError::UnexpectedEOF);
+            // A growable inode behaves like a disk inode and simply reports
+            // the end of file, while a fixed buffer cannot be read beyond.
+            return if self.growable {
+                Ok(0)
+            } else {
+                Err(OsError::UnexpectedEOF)
+            };
         }
 
         let len = min(lock.len() - off, buf.len());
@@ -91,8 +140,19 @@ impl Vnode for Inode {
     }
 
     fn write_at(&self, buf: &[u8], off: usize) -> Result<usize> {
+        if self.deny_write.load(SeqCst) > 0 {
+            return Err(OsError::InvalidFileMode);
+        }
+
         // Protect during the whole process.
         let mut lock = self.buf.lock();
+        let end = off.checked_add(buf.len()).ok_or(OsError::FileTooLarge)?;
+        if self.growable && end > lock.len() {
+            if end > MAX_LEN {
+                return Err(OsError::FileTooLarge);
+            }
+            lock.resize(end, 0);
+        }
         if off >= lock.len() {
             return Err(OsError::UnexpectedEOF);
         }
@@ -104,15 +164,28 @@ impl Vnode for Inode {
         Ok(len)
     }
 
-    fn resize(&self, _size: usize) -> Result<()> {
-        unimplemented!();
+    fn resize(&self, size: usize) -> Result<()> {
+        if !self.growable {
+            return Err(OsError::InvalidFileMode);
+        }
+        if size > MAX_LEN {
+            return Err(OsError::FileTooLarge);
+        }
+
+        let mut lock = self.buf.lock();
+        lock.resize(size, 0);
+        lock.shrink_to_fit();
+        Ok(())
     }
 
-    fn close(&self) {
-        unimplemented!();
+    // The buffer is released along with the last reference to the inode.
+    fn close(&self) {}
+
+    fn deny_write(&self) {
+        self.deny_write.fetch_add(1, SeqCst);
     }
 
-    // TODO: Impl deny write for mem file.
-    fn deny_write(&self) {}
-    fn allow_write(&self) {}
+    fn allow_write(&self) {
+        self.deny_write.fetch_sub(1, SeqCst);
+    }
 }
@@ -0,0 +1,181 @@
+//! Pipes.
+//!
+//! A pipe is a bounded byte queue with a read end and a write end, each of
+//! which is a [`Vnode`]. Every [`File`] on an end shares its vnode, so an
+//! end is closed once its last [`File`] is dropped:
+//!
+//! - Reading an empty pipe blocks until data arrives, or returns `Ok(0)`
+//!   (EOF) once the write end is closed.
+//! - Writing a full pipe blocks until a reader makes room, or fails with
+//!   [`OsError::BrokenPipe`] once the read end is closed.
+//! - Either wait fails with [`OsError::Interrupted`] once the thread is
+//!   [`interrupt`](crate::thread::Thread::interrupt)ed.
+//!
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::cmp::min;
+
+use super::inmem::alloc_inum;
+use super::{File, FileType, Vnode};
+use crate::mem::PG_SIZE;
+use crate::sync::{Condvar, Mutex};
+use crate::{OsError, Result};
+
+/// Capacity of a pipe in bytes.
+pub const PIPE_SIZE: usize = PG_SIZE;
+
+/// Creates a pipe, and returns its read end and write end.
+pub fn pipe() -> (File, File) {
+    let pipe = Arc::new(Pipe {
+        inum: alloc_inum(),
+        state: Mutex::new(State {
+            buf: VecDeque::with_capacity(PIPE_SIZE),
+            reader: true,
+            writer: true,
+        }),
+        readable: Condvar::new(),
+        writable: Condvar::new(),
+    });
+
+    (
+        File::new(Arc::new(ReadEnd(pipe.clone()))),
+        File::new(Arc::new(WriteEnd(pipe))),
+    )
+}
+
+struct Pipe {
+    inum: usize,
+    state: Mutex<State>,
+    /// Notified when data arrives or the write end closes.
+    readable: Condvar,
+    /// Notified when room is made or the read end closes.
+    writable: Condvar,
+}
+
+struct State {
+    buf: VecDeque<u8>,
+    /// Whether the read end is still open.
+    reader: bool,
+    /// Whether the write end is still open.
+    writer: bool,
+}
+
+struct ReadEnd(Arc<Pipe>);
+struct WriteEnd(Arc<Pipe>);
+
+impl Drop for ReadEnd {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.reader = false;
+        self.0.writable.notify_all();
+    }
+}
+
+impl Drop for WriteEnd {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.writer = false;
+        self.0.readable.notify_all();
+    }
+}
+
+impl Vnode for ReadEnd {
+    fn read_at(&self, buf: &mut [u8], _off: usize) -> Result<usize> {
+        let pipe = &self.0;
+        let mut state = pipe.state.lock();
+        while state.buf.is_empty() && state.writer && !buf.is_empty() {
+            if pipe.readable.wait_interruptible(&mut state) {
+                return Err(OsError::Interrupted);
+            }
+        }
+
+        let len = min(buf.len(), state.buf.len());
+        buf.iter_mut()
+            .zip(state.buf.drain(..len))
+            .for_each(|(dst, src)| *dst = src);
+        pipe.writable.notify_all();
+        Ok(len)
+    }
+
+    fn write_at(&self, _buf: &[u8], _off: usize) -> Result<usize> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn deny_write(&self) {}
+    fn allow_write(&self) {}
+
+    fn inum(&self) -> usize {
+        self.0.inum
+    }
+
+    fn len(&self) -> usize {
+        self.0.state.lock().buf.len()
+    }
+
+    fn resize(&self, _size: usize) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn close(&self) {}
+
+    fn kind(&self) -> FileType {
+        FileType::Pipe
+    }
+}
+
+impl Vnode for WriteEnd {
+    fn read_at(&self, _buf: &mut [u8], _off: usize) -> Result<usize> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    /// Blocks until all of `buf` is written. If the read end closes midway,
+    /// returns how much was written, or an error if nothing was.
+    fn write_at(&self, buf: &[u8], _off: usize) -> Result<usize> {
+        let pipe = &self.0;
+        let mut state = pipe.state.lock();
+        let mut written = 0;
+
+        while written < buf.len() {
+            while state.buf.len() == PIPE_SIZE && state.reader {
+                if pipe.writable.wait_interruptible(&mut state) {
+                    return Err(OsError::Interrupted);
+                }
+            }
+            if !state.reader {
+                break;
+            }
+
+            let len = min(buf.len() - written, PIPE_SIZE - state.buf.len());
+            state.buf.extend(&buf[written..written + len]);
+            written += len;
+            pipe.readable.notify_all();
+        }
+
+        match written {
+            0 if !buf.is_empty() => Err(OsError::BrokenPipe),
+            n => Ok(n),
+        }
+    }
+
+    fn deny_write(&self) {}
+    fn allow_write(&self) {}
+
+    fn inum(&self) -> usize {
+        self.0.inum
+    }
+
+    fn len(&self) -> usize {
+        self.0.state.lock().buf.len()
+    }
+
+    fn resize(&self, _size: usize) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn close(&self) {}
+
+    fn kind(&self) -> FileType {
+        FileType::Pipe
+    }
+}
@@ -0,0 +1,348 @@
+//! Process file system.
+//!
+//! A synthetic, read-only file system exposing kernel state. The content of
+//! a file is generated when it is opened, so a [`File`] is a snapshot that
+//! doesn't change while being read. Layout:
+//!
+//! ```text
+//! /threads        all threads with their status and priority
+//! /stat           CPU accounting of all threads
+//! /meminfo        page allocator and kernel heap usage
+//! /malloc         kernel heap usage of each block size
+//! /freemap        disk sector usage
+//! /sleepq         sleeping threads and when they wake up
+//! /<tid>/maps     user memory mappings of a process
+//! /self/maps      user memory mappings of the current process
+//! ```
+//!
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::fmt::Write;
+
+use super::disk::{Path, DISKFS};
+use super::inmem::alloc_inum;
+use super::{components, File, FileSys, FileType, Vnode};
+use crate::mem::malloc::Heap;
+use crate::mem::palloc::{Palloc, UserPool};
+use crate::mem::{PTEFlags, PG_SIZE};
+use crate::sbi::timer::{timer_ticks, CLOCK_PER_TICK};
+use crate::sleepq::SLEEP_QUEUE;
+use crate::sync::Lazy;
+use crate::thread::{self, Manager, Thread};
+use crate::{OsError, Result};
+
+/// Global proc filesys, mounted at [`super::PROCFS_ROOT`].
+pub static PROCFS: Lazy<ProcFs> = Lazy::new(|| ProcFs::mount(()).expect("Proc fs mounting failed"));
+
+/// Generates the content of a file.
+type Generator = fn() -> String;
+
+/// Files at the root of procfs, and how their contents are generated.
+const ROOT_FILES: [(&str, Generator); 6] = [
+    ("threads", threads),
+    ("stat", stat),
+    ("meminfo", meminfo),
+    ("malloc", malloc),
+    ("freemap", freemap),
+    ("sleepq", sleepq),
+];
+
+/// Proc file system.
+///
+/// # See
+/// [`crate::fs::procfs::PROCFS`].
+pub struct ProcFs;
+
+impl ProcFs {
+    /// Lists entries of the directory at `path`.
+    pub fn read_dir(&self, path: Path) -> Result<Vec<(String, FileType)>> {
+        let names: Vec<&str> = components(&path).collect();
+        match names[..] {
+            [] => {
+                let files = ROOT_FILES
+                    .iter()
+                    .map(|(name, _)| (name.to_string(), FileType::File));
+                let procs = Manager::get()
+                    .all()
+                    .into_iter()
+                    .filter(|t| t.pagetable.is_some())
+                    .map(|t| (t.id().to_string(), FileType::Dir));
+                let current = ("self".to_string(), FileType::Dir);
+                Ok(files.chain(procs).chain([current]).collect())
+            }
+            [proc] => {
+                process(proc)?;
+                Ok(Vec::from([("maps".to_string(), FileType::File)]))
+            }
+            _ => Err(OsError::NotADirectory),
+        }
+    }
+}
+
+impl FileSys for ProcFs {
+    type Device = ();
+    type Path = Path;
+
+    fn mount(_device: Self::Device) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn unmount(&self) {}
+
+    fn open(&self, id: Self::Path) -> Result<File> {
+        let names: Vec<&str> = components(&id).collect();
+        let content = match names[..] {
+            [name] => {
+                let (_, generate) =
+                    ROOT_FILES.iter().find(|(n, _)| *n == name).ok_or_else(|| {
+                        match process(name) {
+                            Ok(_) => OsError::IsADirectory,
+                            Err(e) => e,
+                        }
+                    })?;
+                generate()
+            }
+            [proc, "maps"] => maps(&*process(proc)?),
+            [] => return Err(OsError::IsADirectory),
+            _ => return Err(OsError::NoSuchFile),
+        };
+
+        Ok(File::new(Arc::new(Snapshot::new(content))))
+    }
+
+    fn close(&self, _file: File) {}
+
+    fn create(&self, _id: Self::Path) -> Result<File> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn remove(&self, _id: Self::Path) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+}
+
+/// Finds the process named by a directory, either a tid or `self`.
+fn process(name: &str) -> Result<Arc<Thread>> {
+    let thread = match name {
+        "self" => thread::current(),
+        tid => {
+            let tid: isize = tid.parse().or(Err(OsError::NoSuchFile))?;
+            Manager::get()
+                .all()
+                .into_iter()
+                .find(|t| t.id() == tid)
+                .ok_or(OsError::NoSuchFile)?
+        }
+    };
+
+    match thread.pagetable {
+        Some(_) => Ok(thread),
+        None => Err(OsError::NoSuchFile),
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                  Contents                                  */
+/* -------------------------------------------------------------------------- */
+
+fn threads() -> String {
+    let mut s = format!(
+        "{:>4} {:<16} {:<8} {:>3} USER\n",
+        "TID", "NAME", "STATUS", "PRI"
+    );
+    for t in Manager::get().all() {
+        let _ = writeln!(
+            s,
+            "{:>4} {:<16} {:<8} {:>3} {}",
+            t.id(),
+            t.name(),
+            format!("{:?}", t.status()),
+            t.priority.load(core::sync::atomic::Ordering::SeqCst),
+            if t.userproc.is_some() { "yes" } else { "no" },
+        );
+    }
+    s
+}
+
+fn stat() -> String {
+    let mut s = format!(
+        "{:>4} {:<16} {:>8} {:>6} {:>6} {:>12} {:>12} {:>4}\n",
+        "TID", "NAME", "TICKS", "VCSW", "IVCSW", "READY_US", "BLOCKED_US", "DON"
+    );
+    for t in Manager::get().all() {
+        let stats = t.stats();
+        let _ = writeln!(
+            s,
+            "{:>4} {:<16} {:>8} {:>6} {:>6} {:>12} {:>12} {:>4}",
+            t.id(),
+            t.name(),
+            stats.ticks,
+            stats.voluntary,
+            stats.involuntary,
+            stats.ready_us,
+            stats.blocked_us,
+            stats.donations,
+        );
+    }
+    s
+}
+
+fn meminfo() -> String {
+    let heap = Heap::get();
+    format!(
+        "palloc:   {} / {} pages\n\
+         userpool: {} / {} pages\n\
+         heap:     {} allocated, {} free, {} total bytes\n",
+        Palloc::allocated(),
+        Palloc::total(),
+        UserPool::allocated(),
+        UserPool::total(),
+        heap.allocated(),
+        heap.free(),
+        heap.total(),
+    )
+}
+
+fn malloc() -> String {
+    // Take the statistics before formatting, which allocates from the heap.
+    let stats = Heap::get().desc_stats();
+    let (spilled, spilled_pages) = Heap::get().spilled();
+
+    let mut s = format!(
+        "{:>6} {:>10} {:>10} {:>10}\n",
+        "BLOCK", "ALLOCATED", "FREE", "TOTAL"
+    );
+    for stat in stats {
+        let _ = writeln!(
+            s,
+            "{:>6} {:>10} {:>10} {:>10}",
+            stat.block_size, stat.allocated, stat.free, stat.total
+        );
+    }
+    let _ = writeln!(
+        s,
+        "{:>6} {:>10} {:>10} {:>10}",
+        "large",
+        spilled,
+        (spilled_pages * PG_SIZE).saturating_sub(spilled),
+        spilled_pages * PG_SIZE
+    );
+    s
+}
+
+fn freemap() -> String {
+    let (used, total) = DISKFS.usage();
+    format!("sectors: {} / {} used\n", used, total)
+}
+
+fn sleepq() -> String {
+    let mut sleeping: Vec<_> = SLEEP_QUEUE
+        .lock()
+        .iter()
+        .map(|data| (data.clock / CLOCK_PER_TICK, data.thread.clone()))
+        .collect();
+    sleeping.sort_by_key(|(ticks, _)| *ticks);
+
+    let mut s = format!("now: {}\n{:>8} {:>4} NAME\n", timer_ticks(), "WAKE", "TID");
+    for (ticks, t) in sleeping {
+        let _ = writeln!(s, "{:>8} {:>4} {}", ticks, t.id(), t.name());
+    }
+    s
+}
+
+/// Lists user mappings of `proc`, merging adjacent pages of the same rights.
+fn maps(proc: &Thread) -> String {
+    let mappings = proc.pagetable.as_ref().unwrap().lock().user_mappings();
+
+    let rights = PTEFlags::R | PTEFlags::W | PTEFlags::X;
+    let mut ranges: Vec<(usize, usize, PTEFlags)> = Vec::new();
+    for (va, entry) in mappings {
+        let flags = entry.flag() & rights;
+        match ranges.last_mut() {
+            Some((_, end, f)) if *end == va && *f == flags => *end += PG_SIZE,
+            _ => ranges.push((va, va + PG_SIZE, flags)),
+        }
+    }
+
+    let mut s = String::new();
+    for (start, end, flags) in ranges {
+        let _ = writeln!(
+            s,
+            "{:#010x}-{:#010x} {}{}{}",
+            start,
+            end,
+            if flags.contains(PTEFlags::R) {
+                'r'
+            } else {
+                '-'
+            },
+            if flags.contains(PTEFlags::W) {
+                'w'
+            } else {
+                '-'
+            },
+            if flags.contains(PTEFlags::X) {
+                'x'
+            } else {
+                '-'
+            },
+        );
+    }
+    s
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                    Inode                                   */
+/* -------------------------------------------------------------------------- */
+
+/// A read-only inode over generated content.
+struct Snapshot {
+    inum: usize,
+    data: Box<[u8]>,
+}
+
+impl Snapshot {
+    fn new(content: String) -> Self {
+        Self {
+            inum: alloc_inum(),
+            data: content.into_bytes().into_boxed_slice(),
+        }
+    }
+}
+
+impl Vnode for Snapshot {
+    fn inum(&self) -> usize {
+        self.inum
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn read_at(&self, buf: &mut [u8], off: usize) -> Result<usize> {
+        if off >= self.data.len() {
+            return Ok(0);
+        }
+
+        let len = min(self.data.len() - off, buf.len());
+        buf[..len].copy_from_slice(&self.data[off..off + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, _buf: &[u8], _off: usize) -> Result<usize> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn resize(&self, _size: usize) -> Result<()> {
+        Err(OsError::InvalidFileMode)
+    }
+
+    fn close(&self) {}
+    fn deny_write(&self) {}
+    fn allow_write(&self) {}
+}
@@ -0,0 +1,198 @@
+//! Temporary file system.
+//!
+//! A RAM-backed file system with a directory tree. Regular files are the
+//! growable [`inmem`](super::inmem) inodes, so all contents live on the
+//! kernel heap and never touch the disk image.
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::disk::Path;
+use super::inmem::{alloc_inum, Inode};
+use super::{components, File, FileSys, FileType, Stat, Vnode};
+use crate::sync::{Lazy, Mutex};
+use crate::{OsError, Result};
+
+/// Global tmp filesys, mounted at [`super::TMPFS_ROOT`].
+///
+/// # Usage
+/// ```ignore
+/// TMPFS.mkdir("/logs".into())?;
+/// let mut file = TMPFS.create("/logs/boot".into())?;
+/// file.write_all(b"hello")?;
+/// let entries = TMPFS.read_dir("/logs".into())?;
+/// TMPFS.remove("/logs/boot".into())?;
+/// ```
+pub static TMPFS: Lazy<TmpFs> = Lazy::new(|| TmpFs::mount(()).expect("Tmp fs mounting failed"));
+
+/// In-memory file system.
+///
+/// # See
+/// [`crate::fs::tmpfs::TMPFS`].
+pub struct TmpFs {
+    root: Arc<Dir>,
+}
+
+/// An object a directory entry refers to.
+#[derive(Clone)]
+enum Node {
+    File(Arc<Inode>),
+    Dir(Arc<Dir>),
+}
+
+impl Node {
+    fn stat(&self) -> Stat {
+        match self {
+            Node::File(inode) => Stat {
+                inum: inode.inum(),
+                len: inode.len(),
+                kind: FileType::File,
+            },
+            Node::Dir(dir) => Stat {
+                inum: dir.inum,
+                len: 0,
+                kind: FileType::Dir,
+            },
+        }
+    }
+}
+
+/// A directory, mapping names to nodes.
+struct Dir {
+    inum: usize,
+    entries: Mutex<BTreeMap<String, Node>>,
+}
+
+impl Dir {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            inum: alloc_inum(),
+            entries: Mutex::new(BTreeMap::new()),
+        })
+    }
+}
+
+impl TmpFs {
+    /// Resolves `path` to the node it names.
+    fn lookup(&self, path: &str) -> Result<Node> {
+        self.walk(components(path))
+    }
+
+    /// Walks down from the root directory through `names`.
+    fn walk<'a>(&self, names: impl Iterator<Item = &'a str>) -> Result<Node> {
+        let mut node = Node::Dir(self.root.clone());
+        for name in names {
+            let next = match &node {
+                Node::Dir(dir) => dir.entries.lock().get(name).cloned(),
+                Node::File(_) => return Err(OsError::NotADirectory),
+            };
+            node = next.ok_or(OsError::NoSuchFile)?;
+        }
+        Ok(node)
+    }
+
+    /// Resolves the parent directory of `path`, and returns it along with
+    /// the last component of `path`.
+    fn parent<'a>(&self, path: &'a str) -> Result<(Arc<Dir>, &'a str)> {
+        let mut names: Vec<&str> = components(path).collect();
+        let name = names.pop().ok_or(OsError::IsADirectory)?;
+        match self.walk(names.into_iter())? {
+            Node::Dir(dir) => Ok((dir, name)),
+            Node::File(_) => Err(OsError::NotADirectory),
+        }
+    }
+
+    /// Creates a directory at `path`. Its parent must exist.
+    pub fn mkdir(&self, path: Path) -> Result<()> {
+        let (parent, name) = self.parent(&path)?;
+        let mut entries = parent.entries.lock();
+        if entries.contains_key(name) {
+            return Err(OsError::CreateExistInode);
+        }
+        entries.insert(name.to_string(), Node::Dir(Dir::new()));
+        Ok(())
+    }
+
+    /// Lists entries of the directory at `path`, sorted by name.
+    pub fn read_dir(&self, path: Path) -> Result<Vec<(String, FileType)>> {
+        match self.lookup(&path)? {
+            Node::Dir(dir) => Ok(dir
+                .entries
+                .lock()
+                .iter()
+                .map(|(name, node)| (name.clone(), node.stat().kind))
+                .collect()),
+            Node::File(_) => Err(OsError::NotADirectory),
+        }
+    }
+
+    /// Gets metadata of the object at `path`.
+    pub fn metadata(&self, path: Path) -> Result<Stat> {
+        self.lookup(&path).map(|node| node.stat())
+    }
+}
+
+impl FileSys for TmpFs {
+    type Device = ();
+    type Path = Path;
+
+    fn mount(_device: Self::Device) -> Result<Self> {
+        Ok(Self { root: Dir::new() })
+    }
+
+    /// Drops every entry. Files that are still open stay readable
+    /// until their last [`File`] is dropped.
+    fn unmount(&self) {
+        self.root.entries.lock().clear();
+    }
+
+    fn open(&self, id: Self::Path) -> Result<File> {
+        match self.lookup(&id)? {
+            Node::File(inode) => Ok(File::new(inode)),
+            Node::Dir(_) => Err(OsError::IsADirectory),
+        }
+    }
+
+    fn close(&self, _file: File) {}
+
+    fn create(&self, id: Self::Path) -> Result<File> {
+        let (parent, name) = self.parent(&id)?;
+        let mut entries = parent.entries.lock();
+
+        let inode = match entries.get(name) {
+            // Trunc existing file to 0 on create.
+            Some(Node::File(inode)) => {
+                inode.resize(0)?;
+                inode.clone()
+            }
+            Some(Node::Dir(_)) => return Err(OsError::IsADirectory),
+            None => {
+                let inode = Inode::growable();
+                entries.insert(name.to_string(), Node::File(inode.clone()));
+                inode
+            }
+        };
+
+        Ok(File::new(inode))
+    }
+
+    fn remove(&self, id: Self::Path) -> Result<()> {
+        let (parent, name) = self.parent(&id)?;
+        let mut entries = parent.entries.lock();
+
+        match entries.get(name) {
+            None => return Err(OsError::NoSuchFile),
+            Some(Node::Dir(dir)) if !dir.entries.lock().is_empty() => {
+                return Err(OsError::DirectoryNotEmpty)
+            }
+            _ => {}
+        }
+
+        // Opened files hold their inode, which is freed on last close.
+        entries.remove(name);
+        Ok(())
+    }
+}
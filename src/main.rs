@@ -18,6 +18,7 @@ pub mod mem;
 pub mod sleepq;
 pub mod sync;
 pub mod thread;
+pub mod timer;
 pub mod trap;
 pub mod userproc;
 
@@ -43,6 +44,10 @@ extern "C" {
 
 pub type Result<T> = core::result::Result<T, OsError>;
 
+/// The first user program, run with feature `shell`.
+#[cfg(feature = "shell")]
+const INIT: &str = "init";
+
 /// Initializes major components of our kernel
 ///
 /// Note: `extern "C"` ensures this function adhere to the C calling convention.
@@ -72,7 +77,7 @@ pub extern "C" fn main(hart_id: usize, dtb: usize) -> ! {
     };
     assert_eq!(pm_base, mem::PM_BASE, "Error constant mem::PM_BASE.");
     // Get the boot arguments.
-    let _bootargs: &'static str = unsafe {
+    let bootargs: &'static str = unsafe {
         let (vm, len) = {
             let bootargs = devtree.chosen().bootargs().unwrap();
             let len = bootargs.len();
@@ -89,7 +94,17 @@ pub extern "C" fn main(hart_id: usize, dtb: usize) -> ! {
     #[cfg(feature = "debug")]
     {
         kprintln!("RAM: 0x{:x} - 0x{:x}", ram_base, ram_tail);
-        kprintln!("BOOTARGS: {:?}", _bootargs);
+        kprintln!("BOOTARGS: {:?}", bootargs);
+    }
+
+    // Pick the scheduling policy, `sched=<policy>` in the boot arguments.
+    for arg in bootargs.split_whitespace() {
+        if let Some(name) = arg.strip_prefix("sched=") {
+            match name.parse() {
+                Ok(policy) => thread::scheduler::set_policy(policy),
+                Err(_) => kprintln!("Unknown scheduling policy: {}", name),
+            }
+        }
     }
 
     trap::set_strap_entry();
@@ -100,6 +115,7 @@ pub extern "C" fn main(hart_id: usize, dtb: usize) -> ! {
     };
 
     device::plic::init(hart_id);
+    device::uart::init();
     #[cfg(feature = "debug")]
     kprintln!("Virtio inited.");
 
@@ -111,36 +127,21 @@ pub extern "C" fn main(hart_id: usize, dtb: usize) -> ! {
         use alloc::sync::Arc;
         let sema = Arc::new(sync::Semaphore::new(0));
         let sema2 = sema.clone();
-        thread::spawn("test", move || crate::test::main(sema2, _bootargs));
+        thread::spawn("test", move || crate::test::main(sema2, bootargs));
         sema.down();
     }
 
     #[cfg(feature = "shell")]
     {
-        // TODO: Lab 0
-        loop {
-            use alloc::string::String;
-
-            use crate::thread::Status;
-
-            kprint!("PKUOS>");
-            let mut temp = String::new();
-            let mut newchar;
-            while {
-                newchar = crate::sbi::console_getchar();
-                newchar != 10
-            } {
-                temp.push(char::from_u32(newchar as u32).unwrap());
-            }
-
-            if temp == "whoami" {
-                kprintln!("2300012806");
-            } else if temp == "exit" {
-                break;
-            } else {
-                kprintln!("invalid command")
-            }
-        }
+        use alloc::string::String;
+        use alloc::vec;
+
+        // Boot into the user-space init, which runs the shell. The system
+        // shuts down once init exits.
+        let init = fs::open(INIT).expect("init is not on the disk");
+        let tid = userproc::execute(init, vec![String::from(INIT)]);
+        assert_ne!(tid, -1, "failed to load init");
+        userproc::wait(tid);
     }
 
     DISKFS.unmount();
@@ -162,6 +163,9 @@ unsafe fn panic(info: &core::panic::PanicInfo) -> ! {
     // Report the reason for invoking `panic`
     kprintln!("{}", info);
 
+    // A thread with a join handle only fails its joiner.
+    thread::exit_on_panic();
+
     sbi::reset(
         sbi::system_reset::Type::Shutdown,
         sbi::system_reset::Reason::SystemFailure,
@@ -119,6 +119,15 @@ impl Desc {
     }
 }
 
+/// Usage of a heap descriptor, in bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DescStat {
+    pub block_size: usize,
+    pub allocated: usize,
+    pub free: usize,
+    pub total: usize,
+}
+
 /// An elastic kernel heap. It's a memory allocator more fine-grained than [`Palloc`].
 ///
 /// It's able to serve requests of any size. For requests no larger than 1024 bytes,
@@ -217,6 +226,27 @@ impl Heap {
             total + desc.total
         }) + self.spilled_total.load(Relaxed) * PG_SIZE
     }
+
+    /// Usage of every descriptor, from the smallest block size to the largest
+    pub fn desc_stats(&self) -> [DescStat; 8] {
+        core::array::from_fn(|idx| {
+            let desc = self.descs[idx].lock();
+            DescStat {
+                block_size: desc.block_size,
+                allocated: desc.allocated,
+                free: desc.free,
+                total: desc.total,
+            }
+        })
+    }
+
+    /// Bytes and pages taken by requests larger than [`MAX_BLKSIZE`]
+    pub fn spilled(&self) -> (usize, usize) {
+        (
+            self.spilled_alloc.load(Relaxed),
+            self.spilled_total.load(Relaxed),
+        )
+    }
 }
 
 unsafe impl Send for Heap {}
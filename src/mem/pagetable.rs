@@ -21,11 +21,12 @@
 
 mod entry;
 
+use alloc::vec::Vec;
 use core::ptr;
 use core::{arch::asm, mem::transmute};
 
 use crate::mem::{
-    layout::{MMIO_BASE, PLIC_BASE, VM_BASE},
+    layout::{MMIO_BASE, PLIC_BASE, UART_BASE, VM_BASE},
     malloc::{kalloc, kfree},
     palloc::UserPool,
     utils::{PageAlign, PhysAddr, PG_SIZE},
@@ -87,6 +88,66 @@ impl PageTable {
         })
     }
 
+    /// Removes the mapping of `va`, and returns the physical address it was
+    /// mapped to. The page itself is not freed.
+    pub fn unmap(&mut self, va: usize) -> Option<PhysAddr> {
+        let l0_table = self.walk(Self::px(2, va))?.walk(Self::px(1, va))?;
+        let entry = l0_table.entries.get_mut(Self::px(0, va))?;
+        if !entry.is_valid() {
+            return None;
+        }
+
+        let pa = entry.pa();
+        entry.set_invalid();
+        unsafe { asm!("sfence.vma {va}, zero", va = in(reg) va) };
+        Some(pa)
+    }
+
+    /// Rewrites the flags of the leaf mapping of `va`. Returns whether there
+    /// is one.
+    pub fn set_flags(&mut self, va: usize, flags: PTEFlags) -> bool {
+        let l0_table = match self
+            .walk(Self::px(2, va))
+            .and_then(|t| t.walk(Self::px(1, va)))
+        {
+            Some(table) => table,
+            None => return false,
+        };
+        let entry = &mut l0_table.entries[Self::px(0, va)];
+        if !entry.is_valid() || !entry.is_leaf() {
+            return false;
+        }
+
+        entry.set_flags(flags);
+        unsafe { asm!("sfence.vma {va}, zero", va = in(reg) va) };
+        true
+    }
+
+    /// Collects all user leaf entries, in ascending order of virtual address.
+    pub fn user_mappings(&self) -> Vec<(usize, Entry)> {
+        fn walk(pgt: &PageTable, level: u32, base: usize, out: &mut Vec<(usize, Entry)>) {
+            pgt.entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.is_valid() && !entry.is_global())
+                .for_each(|(idx, entry)| {
+                    let va = base | idx << (PG_SHIFT + 9 * level as usize);
+                    if entry.is_leaf() {
+                        if entry.is_user() {
+                            out.push((va, *entry));
+                        }
+                    } else if level > 0 {
+                        let next = unsafe { PageTable::from_raw(entry.pa().into_va() as *mut _) };
+                        walk(&next, level - 1, va, out);
+                    }
+                });
+        }
+
+        let mut mappings = Vec::new();
+        walk(self, 2, 0, &mut mappings);
+        mappings
+    }
+
     /// Free all memory used by this pagetable back to where they were allocated.
     pub unsafe fn destroy(&mut self) {
         unsafe fn destroy_imp(pgt: &mut PageTable, level: usize) {
@@ -216,6 +277,9 @@ impl KernelPgTable {
         // virtio mmio disk interface
         root.map(PhysAddr::from(MMIO_BASE), MMIO_BASE, PG_SIZE, rw);
 
+        // uart registers
+        root.map(PhysAddr::from(UART_BASE), UART_BASE, PG_SIZE, rw);
+
         root.activate();
         root
     }
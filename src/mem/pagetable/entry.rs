@@ -38,7 +38,7 @@ impl Entry {
         Entry((((pa.value() >> PG_SHIFT) & PPN_MASK) << Self::FLAG_SHIFT) | flags.bits())
     }
 
-    fn flag(&self) -> PTEFlags {
+    pub fn flag(&self) -> PTEFlags {
         PTEFlags::from_bits_truncate(self.0)
     }
 
@@ -88,6 +88,11 @@ impl Entry {
         self.0 &= !PTEFlags::A.bits;
     }
 
+    /// Replaces all flags, keeping the physical address.
+    pub fn set_flags(&mut self, flags: PTEFlags) {
+        self.0 = self.0 & !PTEFlags::all().bits | flags.bits();
+    }
+
     /// A PTE is a leaf PTE when at least one bit in R, W and X
     /// is set; otherwise, it is a pointer to the next level of
     /// the page table.
@@ -55,6 +55,12 @@ impl BuddyAllocator {
 
     /// Allocate n pages and returns the virtual address.
     unsafe fn alloc(&mut self, n: usize) -> *mut u8 {
+        self.try_alloc(n)
+            .unwrap_or_else(|| unreachable!("memory is exhausted"))
+    }
+
+    /// Like [`alloc`](Self::alloc), but returns `None` if memory is exhausted.
+    unsafe fn try_alloc(&mut self, n: usize) -> Option<*mut u8> {
         assert!(n <= 1 << MAX_ORDER, "request is too large");
 
         let order = n.next_power_of_two().trailing_zeros() as usize;
@@ -72,11 +78,11 @@ impl BuddyAllocator {
                     }
                 }
                 self.allocated += 1 << order;
-                return self.free_lists[order].pop().unwrap().cast();
+                return Some(self.free_lists[order].pop().unwrap().cast());
             }
         }
 
-        unreachable!("memory is exhausted");
+        None
     }
 
     /// Deallocate a chunk of pages
@@ -133,6 +139,16 @@ impl Palloc {
         Self::instance().lock().dealloc(ptr, n)
     }
 
+    /// The number of pages allocated
+    pub fn allocated() -> usize {
+        Self::instance().lock().allocated
+    }
+
+    /// The number of pages managed by this allocator
+    pub fn total() -> usize {
+        Self::instance().lock().total / PG_SIZE
+    }
+
     fn instance() -> &'static Mutex<BuddyAllocator, Intr> {
         static PALLOC: Palloc = Palloc(Lazy::new(|| Mutex::new(BuddyAllocator::empty())));
 
@@ -150,6 +166,11 @@ impl UserPool {
         Self::instance().lock().alloc(n)
     }
 
+    /// Allocate n pages of consecutive space, unless memory is exhausted.
+    pub unsafe fn try_alloc_pages(n: usize) -> Option<*mut u8> {
+        Self::instance().lock().try_alloc(n)
+    }
+
     /// Free n pages of memory starting at `ptr`
     pub unsafe fn dealloc_pages(ptr: *mut u8, n: usize) {
         Self::instance().lock().dealloc(ptr, n)
@@ -160,6 +181,22 @@ impl UserPool {
         Self::instance().lock().insert_range(start, end);
     }
 
+    /// The number of pages allocated
+    pub fn allocated() -> usize {
+        Self::instance().lock().allocated
+    }
+
+    /// The number of pages managed by this allocator
+    pub fn total() -> usize {
+        Self::instance().lock().total / PG_SIZE
+    }
+
+    /// The number of pages not allocated yet
+    pub fn free() -> usize {
+        let pool = Self::instance().lock();
+        pool.total / PG_SIZE - pool.allocated
+    }
+
     fn instance() -> &'static Mutex<BuddyAllocator, Intr> {
         static USERPOOL: UserPool = UserPool(Lazy::new(|| Mutex::new(BuddyAllocator::empty())));
 
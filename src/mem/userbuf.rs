@@ -1,20 +1,33 @@
 #![allow(dead_code)]
 
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::arch::global_asm;
 
 use crate::error::OsError;
-use crate::mem::in_kernel_space;
+use crate::mem::{get_pte, in_kernel_space, PG_SIZE};
 use crate::Result;
 
+/// Longest string accepted by [`read_user_str`], including the terminating NUL.
+pub const USER_STR_MAX: usize = PG_SIZE;
+
+/// Checks that `va` is neither in kernel space nor on a page mapped without
+/// [`PTEFlags::U`](crate::mem::PTEFlags::U), such as a guard page, which the
+/// kernel could access. Unmapped pages are left to the page fault handler.
+fn check_user(va: usize) -> Result<()> {
+    if in_kernel_space(va) || get_pte(va).map_or(false, |e| e.is_valid() && !e.is_user()) {
+        return Err(OsError::BadPtr);
+    }
+    Ok(())
+}
+
 /// Read a single byte from user space.
 ///
 /// ## Return
 /// - `Ok(byte)`
 /// - `Err`: A page fault happened.
 fn read_user_byte(user_src: *const u8) -> Result<u8> {
-    if in_kernel_space(user_src as usize) {
-        return Err(OsError::BadPtr);
-    }
+    check_user(user_src as usize)?;
 
     let byte: u8 = 0;
     let ret_status: u8 = unsafe { __knrl_read_usr_byte(user_src, &byte as *const u8) };
@@ -32,9 +45,7 @@ fn read_user_byte(user_src: *const u8) -> Result<u8> {
 /// - `Ok(())`
 /// - `Err`: A page fault happened.
 fn write_user_byte(user_src: *const u8, value: u8) -> Result<()> {
-    if in_kernel_space(user_src as usize) {
-        return Err(OsError::BadPtr);
-    }
+    check_user(user_src as usize)?;
 
     let ret_status: u8 = unsafe { __knrl_write_usr_byte(user_src, value) };
 
@@ -45,6 +56,40 @@ fn write_user_byte(user_src: *const u8, value: u8) -> Result<()> {
     }
 }
 
+/// Copy a NUL-terminated string from user space.
+///
+/// ## Errors
+/// - [`OsError::BadPtr`]: a page fault happened.
+/// - [`OsError::ArgumentTooLong`]: no NUL within [`USER_STR_MAX`] bytes.
+/// - [`OsError::CstrFormatErr`]: the string is not valid UTF-8.
+pub fn read_user_str(user_src: *const u8) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match read_user_byte(user_src.wrapping_add(bytes.len()))? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+        if bytes.len() >= USER_STR_MAX {
+            return Err(OsError::ArgumentTooLong);
+        }
+    }
+    String::from_utf8(bytes).or(Err(OsError::CstrFormatErr))
+}
+
+/// Copy `len` bytes from user space into a kernel buffer.
+pub fn read_user_buf(user_src: *const u8, len: usize) -> Result<Vec<u8>> {
+    (0..len)
+        .map(|i| read_user_byte(user_src.wrapping_add(i)))
+        .collect()
+}
+
+/// Copy `buf` into user space.
+pub fn write_user_buf(user_dst: *mut u8, buf: &[u8]) -> Result<()> {
+    buf.iter()
+        .enumerate()
+        .try_for_each(|(i, byte)| write_user_byte(user_dst.wrapping_add(i), *byte))
+}
+
 extern "C" {
     pub fn __knrl_read_usr_byte(user_src: *const u8, byte_ptr: *const u8) -> u8;
     pub fn __knrl_read_usr_byte_pc();
@@ -43,7 +43,7 @@ pub fn set(level: bool) -> bool {
 }
 
 pub fn init() {
-    crate::sbi::timer::next();
+    crate::sbi::timer::init();
 
     set(true);
 
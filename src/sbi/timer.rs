@@ -1,11 +1,14 @@
 //! RISC-V Timer Interface
 
+use alloc::sync::Arc;
 use core::sync::atomic::{AtomicI64, Ordering::SeqCst};
 
 use crate::sbi::set_timer;
 
 pub const TICKS_PER_SEC: usize = 10;
 pub const CLOCK_PRE_SEC: usize = 12500000;
+/// Clock cycles per timer tick.
+pub const CLOCK_PER_TICK: usize = CLOCK_PRE_SEC / TICKS_PER_SEC;
 
 /// Get the clock's raw reading
 pub fn clock() -> usize {
@@ -24,43 +27,66 @@ pub fn time_us() -> usize {
     clock() * 1_000_000 / CLOCK_PRE_SEC
 }
 
-/// Set the next moment when timer interrupt should happen
+/// Converts microseconds to clock cycles.
 #[inline]
+pub fn us_to_clock(us: usize) -> usize {
+    us * (CLOCK_PRE_SEC / 1_000_000)
+}
+
+/// Starts the timer.
+pub fn init() {
+    TICKS.store(timer_ticks(), SeqCst);
+    next();
+}
+
+/// Sets the one-shot timer for the earliest pending event: the wakeup of the
+/// first sleeper, the first [kernel timer](crate::timer), and the end of the
+/// current tick unless the CPU is idle.
+///
+/// Real-time threads keep the tick on even when idle, as their jobs are
+/// released on ticks.
 pub fn next() {
-    set_timer(clock() + CLOCK_PRE_SEC / TICKS_PER_SEC);
+    use crate::sleepq::SLEEP_QUEUE;
+    use crate::thread::{self, scheduler::edf, Manager};
+
+    let wakeup = SLEEP_QUEUE.peek_clock().unwrap_or(usize::MAX);
+    let timer = crate::timer::earliest().map_or(usize::MAX, |t| t as usize * CLOCK_PER_TICK);
+    let idle = Arc::ptr_eq(&thread::current(), Manager::get().idle());
+    let tick_end = if idle && !edf::active() {
+        usize::MAX
+    } else {
+        (clock() / CLOCK_PER_TICK + 1) * CLOCK_PER_TICK
+    };
+    set_timer(wakeup.min(timer).min(tick_end));
 }
 
+/// The last tick handled by [`tick`].
 static TICKS: AtomicI64 = AtomicI64::new(0);
 
 /// Returns the number of timer ticks since booted.
 pub fn timer_ticks() -> i64 {
-    TICKS.load(SeqCst)
+    (clock() / CLOCK_PER_TICK) as i64
 }
 
-/// Increments timer ticks by 1 and sets the next timer interrupt.
+/// Handles a timer interrupt. Runs the per-tick work of every tick passed
+/// since the last interrupt, which may be several after an idle stretch,
+/// wakes up the sleepers due, and sets the next timer interrupt.
 pub fn tick() {
-    TICKS.fetch_add(1, SeqCst);
     use crate::sleepq::SLEEP_QUEUE;
 
     let old = crate::sbi::interrupt::set(false);
     let now = timer_ticks();
 
-    /*
-    kprint!(
-        "TICK while sleepq len {} in {}",
-        SLEEP_QUEUE.len(),
-        crate::thread::current().name()
-    );*/
-
-    while SLEEP_QUEUE.len() != 0 {
-        // kprintln!("IF CALLED");
-        // kprint!("DEBUG: time {}, wakeup at {}", now, SLEEP_QUEUE.peek_time());
-        if now >= SLEEP_QUEUE.peek_time() {
-            let ready_thread = SLEEP_QUEUE.pop().thread;
-            crate::thread::wake_up(ready_thread);
-        } else {
-            break;
-        }
+    for ticks in TICKS.swap(now, SeqCst) + 1..=now {
+        crate::thread::scheduler::mlfqs::tick(ticks);
+        crate::thread::scheduler::edf::tick(ticks);
+        crate::timer::tick(ticks);
+        crate::thread::stats::tick();
+    }
+
+    let clock = clock();
+    while let Some(data) = SLEEP_QUEUE.pop_due(clock) {
+        crate::thread::wake_up(data.thread);
     }
 
     crate::sbi::interrupt::set(old);
@@ -5,7 +5,8 @@ use crate::{
 use alloc::sync::Arc;
 use thread::*;
 pub struct SleepData {
-    pub ticks: i64,
+    /// Clock reading to wake up at.
+    pub clock: usize,
     pub thread: Arc<Thread>,
 }
 
@@ -17,13 +18,13 @@ impl PartialOrd for SleepData {
 
 impl Ord for SleepData {
     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        other.ticks.cmp(&self.ticks)
+        other.clock.cmp(&self.clock)
     }
 }
 
 impl PartialEq for SleepData {
     fn eq(&self, other: &Self) -> bool {
-        self.ticks == other.ticks
+        self.clock == other.clock
     }
 }
 
@@ -42,13 +43,19 @@ impl SleepQueue {
     pub fn len(&self) -> usize {
         self.lock().len()
     }
-    pub fn pop(&self) -> SleepData {
-        self.lock().pop().unwrap()
+    /// Pops the first sleeper if it's due at `clock`.
+    pub fn pop_due(&self, clock: usize) -> Option<SleepData> {
+        let mut queue = self.lock();
+        match queue.peek() {
+            Some(data) if data.clock <= clock => queue.pop(),
+            _ => None,
+        }
     }
     pub fn push(&self, data: SleepData) {
         self.lock().push(data);
     }
-    pub fn peek_time(&self) -> i64 {
-        self.lock().peek().unwrap().ticks.clone()
+    /// When the first sleeper wakes up.
+    pub fn peek_clock(&self) -> Option<usize> {
+        self.lock().peek().map(|data| data.clock)
     }
 }
@@ -29,4 +29,12 @@ pub type Primitive = sleep::Sleep;
 pub trait Lock: Default + Sync + 'static {
     fn acquire(&self);
     fn release(&self);
+
+    /// Acquires the lock unless it takes more than `ticks` timer ticks.
+    /// Returns whether the lock is acquired. Locks that never wait just
+    /// acquire.
+    fn try_acquire_for(&self, _ticks: i64) -> bool {
+        self.acquire();
+        true
+    }
 }
@@ -70,6 +70,45 @@ impl Condvar {
         guard.acquire();
     }
 
+    /// Like [`wait`](Self::wait), but gives up after `ticks` timer ticks.
+    /// Returns whether it timed out.
+    pub fn wait_timeout<T, L: Lock>(&self, guard: &mut MutexGuard<'_, T, L>, ticks: i64) -> bool {
+        let sema = Arc::new(Semaphore::new(0));
+        self.0.borrow_mut().push_front(sema.clone());
+
+        guard.release();
+        let timed_out = sema.down_timeout(ticks);
+        guard.acquire();
+
+        // A notification may have come in after the timeout, and is taken.
+        timed_out && {
+            let mut waiters = self.0.borrow_mut();
+            let len = waiters.len();
+            waiters.retain(|s| !Arc::ptr_eq(s, &sema));
+            waiters.len() != len
+        }
+    }
+
+    /// Like [`wait`](Self::wait), but cut short by
+    /// [`Thread::interrupt`](crate::thread::Thread::interrupt). Returns
+    /// whether it was interrupted.
+    pub fn wait_interruptible<T, L: Lock>(&self, guard: &mut MutexGuard<'_, T, L>) -> bool {
+        let sema = Arc::new(Semaphore::new(0));
+        self.0.borrow_mut().push_front(sema.clone());
+
+        guard.release();
+        let interrupted = sema.down_interruptible();
+        guard.acquire();
+
+        // A notification may have come in after the interrupt, and is taken.
+        interrupted && {
+            let mut waiters = self.0.borrow_mut();
+            let len = waiters.len();
+            waiters.retain(|s| !Arc::ptr_eq(s, &sema));
+            waiters.len() != len
+        }
+    }
+
     /// Wake up one thread from the waiting list
     pub fn notify_one(&self) {
         let length = self.0.borrow().len();
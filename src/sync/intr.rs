@@ -17,6 +17,11 @@ impl Intr {
     pub const fn new() -> Self {
         Self(Cell::new(None))
     }
+
+    /// Whether the lock is acquired, by any thread.
+    pub fn is_held(&self) -> bool {
+        self.0.get().is_some()
+    }
 }
 
 unsafe impl Sync for Intr {}
@@ -1,7 +1,7 @@
 use core::cell::UnsafeCell;
 use core::ops::{Deref, DerefMut};
 
-use crate::sync::{self, Lock};
+use crate::sync::{self, Intr, Lock};
 
 /// A mutual exclusion primitive useful for protecting shared data
 ///
@@ -39,6 +39,24 @@ impl<T, L: Lock> Mutex<T, L> {
         self.lock.acquire();
         MutexGuard(self)
     }
+
+    /// Acquires a mutex, unless it takes more than `ticks` timer ticks.
+    /// Returns `None` if it timed out.
+    pub fn try_lock_for(&self, ticks: i64) -> Option<MutexGuard<'_, T, L>> {
+        if self.lock.try_acquire_for(ticks) {
+            Some(MutexGuard(self))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Mutex<T, Intr> {
+    /// Whether the mutex is locked. Only a thread that locked it and can't
+    /// unlock it, e.g. one that panicked, may use this.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_held()
+    }
 }
 
 /// An RAII implementation of a “scoped lock” of a mutex.
@@ -2,8 +2,9 @@ use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use core::cell::{Cell, RefCell};
 
-use crate::sbi;
+use crate::sbi::{self, timer::timer_ticks};
 use crate::thread::{self, current, Manager, Status, Thread};
+use crate::timer;
 use core::sync::atomic::Ordering;
 
 /// Atomic counting semaphore
@@ -51,6 +52,71 @@ impl Semaphore {
         sbi::interrupt::set(old);
     }
 
+    /// P operation, giving up after `ticks` timer ticks. Returns whether it
+    /// timed out, in which case the current thread is no longer a waiter.
+    pub fn down_timeout(&self, ticks: i64) -> bool {
+        let old = sbi::interrupt::set(false);
+        let deadline = timer_ticks() + ticks;
+
+        while self.value() == 0 {
+            if timer_ticks() >= deadline {
+                sbi::interrupt::set(old);
+                return true;
+            }
+
+            let current = thread::current();
+            self.waiters.borrow_mut().push_front(current.clone());
+
+            // On expiry, take the thread off the waiters and wake it up. The
+            // semaphore outlives the timer, which is cancelled below.
+            let sema = self as *const Self as usize;
+            let timer = timer::add(deadline, move || {
+                unsafe { &*(sema as *const Self) }.remove_waiter(&current);
+            });
+
+            thread::block();
+            timer.cancel();
+        }
+        self.value.set(self.value() - 1);
+
+        sbi::interrupt::set(old);
+        false
+    }
+
+    /// P operation, cut short by [`Thread::interrupt`]. Returns whether it
+    /// was interrupted, in which case the current thread is no longer a
+    /// waiter.
+    pub fn down_interruptible(&self) -> bool {
+        let old = sbi::interrupt::set(false);
+        let current = thread::current();
+
+        while self.value() == 0 {
+            if current.interrupted() {
+                sbi::interrupt::set(old);
+                return true;
+            }
+
+            self.waiters.borrow_mut().push_front(current.clone());
+            current.set_waiting_on(Some(self));
+            thread::block();
+            current.set_waiting_on(None);
+        }
+        self.value.set(self.value() - 1);
+
+        sbi::interrupt::set(old);
+        false
+    }
+
+    /// Takes `thread` off the waiters and wakes it up, if it's waiting.
+    pub(crate) fn remove_waiter(&self, thread: &Arc<Thread>) {
+        let mut waiters = self.waiters.borrow_mut();
+        if let Some(idx) = waiters.iter().position(|t| Arc::ptr_eq(t, thread)) {
+            let thread = waiters.remove(idx).unwrap();
+            drop(waiters);
+            thread::wake_up(thread);
+        }
+    }
+
     /// V operation
     pub fn up(&self) {
         let old = sbi::interrupt::set(false);
@@ -70,6 +70,38 @@ pub fn donation_wrapped(donner: Arc<Thread>, acceptor: Arc<Thread>, lockid: u32)
     }
 }
 
+/// Takes back the donation of `donner` to `acceptor` through lock `lockid`,
+/// if any. The priority of `acceptor` falls back like in
+/// [`release`](Lock::release), to the highest of its other donations and
+/// the priority it had before them.
+pub fn withdraw_donation(donner: &Arc<Thread>, acceptor: &Arc<Thread>, lockid: u32) {
+    let given = |d: &DonationData| {
+        d.lockid == lockid && Arc::ptr_eq(&d.donner, donner) && Arc::ptr_eq(&d.acceptor, acceptor)
+    };
+    donner.donationq.lock().retain(|d| !given(d));
+
+    let mut donations = acceptor.donationq.lock();
+    if !donations.iter().any(|d| !d.is_donner && given(d)) {
+        return;
+    }
+    let received = |d: &&DonationData| !d.is_donner;
+    let prev = donations
+        .iter()
+        .filter(received)
+        .map(|d| d.prev_priority)
+        .min();
+    donations.retain(|d| !given(d));
+    let final_priority = donations
+        .iter()
+        .filter(received)
+        .map(|d| d.donner_priority)
+        .fold(prev.unwrap(), max);
+    drop(donations);
+
+    let setted = *acceptor.priority_setted.lock();
+    acceptor.set_priority(setted.unwrap_or(final_priority));
+}
+
 impl Lock for Sleep {
     fn acquire(&self) {
         if self.holder.borrow().is_some() {
@@ -83,6 +115,25 @@ impl Lock for Sleep {
         self.holder.borrow_mut().replace(thread::current());
     }
 
+    /// Donates like [`acquire`](Lock::acquire). On timeout, the donation is
+    /// taken back from the holder.
+    fn try_acquire_for(&self, ticks: i64) -> bool {
+        let holder = self.holder.borrow().clone();
+        if let Some(acceptor) = holder.clone() {
+            donation_wrapped(current(), acceptor.clone(), self.lockid);
+            crate::thread::Thread::find_and_donate(acceptor, self.lockid);
+        }
+        if self.inner.down_timeout(ticks) {
+            if let Some(acceptor) = holder {
+                withdraw_donation(&current(), &acceptor, self.lockid);
+            }
+            current().delete_donation(self.lockid);
+            return false;
+        }
+        self.holder.borrow_mut().replace(thread::current());
+        true
+    }
+
     /// called in acceptor thread
     fn release(&self) {
         kprintln!("lockid {} is released", self.lockid);
@@ -128,13 +179,13 @@ impl Lock for Sleep {
                 current().id(),
                 current().priority.load(Ordering::SeqCst)
             );
-            current().priority.store(final_priority, Ordering::SeqCst);
+            current().set_priority(final_priority);
         }
 
         if current().priority_setted.lock().is_some() {
             //use setted priority to overwite restored ones
             let setted = current().priority_setted.lock().unwrap();
-            current().priority.store(setted, Ordering::SeqCst);
+            current().set_priority(setted);
         }
 
         self.holder.borrow_mut().take().unwrap();
@@ -1,6 +1,6 @@
 use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
 
-use crate::sbi::interrupt;
+use crate::sbi::{interrupt, timer::timer_ticks};
 use crate::sync::Lock;
 
 /// Spin lock.
@@ -23,4 +23,15 @@ impl Lock for Spin {
     fn release(&self) {
         self.0.store(false, SeqCst);
     }
+
+    fn try_acquire_for(&self, ticks: i64) -> bool {
+        let deadline = timer_ticks() + ticks;
+        while self.0.fetch_or(true, SeqCst) {
+            assert!(interrupt::get(), "may block");
+            if timer_ticks() >= deadline {
+                return false;
+            }
+        }
+        true
+    }
 }
@@ -1,8 +1,10 @@
 //! Kernel Threads
 
 mod imp;
+mod join;
 pub mod manager;
 pub mod scheduler;
+pub mod stats;
 pub mod switch;
 
 use core::{cell::OnceCell, cmp::Reverse, convert::TryInto, ops::DerefMut};
@@ -14,17 +16,23 @@ use crate::{
 };
 
 pub use self::imp::*;
+pub use self::join::{exit_on_panic, JoinHandle};
 pub use self::manager::Manager;
+pub use self::scheduler::edf::wait_next_period;
+pub use self::scheduler::mlfqs::{get_load_avg, get_nice, get_recent_cpu, set_nice};
+pub use self::scheduler::share::{get_tickets, set_tickets, transfer_tickets};
 pub(self) use self::scheduler::{Schedule, Scheduler};
+pub use self::stats::Stats;
 
 use crate::sleepq::*;
 use alloc::sync::{self, Arc};
 use riscv::interrupt;
 
-/// Create a new thread
-pub fn spawn<F>(name: &'static str, f: F) -> Arc<Thread>
+/// Create a new thread, and return a handle to join it
+pub fn spawn<F, T>(name: &'static str, f: F) -> JoinHandle<T>
 where
-    F: FnOnce() + Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
 {
     Builder::new(f).name(name).spawn()
 }
@@ -73,6 +81,7 @@ pub fn block() {
 pub fn wake_up(thread: Arc<Thread>) {
     assert_eq!(thread.status(), Status::Blocked);
     thread.set_status(Status::Ready);
+    thread.counters.wake_up(sbi::timer::clock());
 
     #[cfg(feature = "debug")]
     kprintln!("[THREAD] Wake up {:?}", thread);
@@ -80,11 +89,15 @@ pub fn wake_up(thread: Arc<Thread>) {
     Manager::get().scheduler.lock().register(thread);
 }
 
-/// (Lab1) Sets the current thread's priority to a given value
+/// (Lab1) Sets the current thread's priority to a given value, at most
+/// [`PRI_MAX`]. Ignored by the MLFQS scheduler, which computes priorities
+/// itself.
 pub fn set_priority(_priority: u32) {
-    current()
-        .priority
-        .store(_priority, core::sync::atomic::Ordering::SeqCst);
+    if scheduler::policy() == scheduler::Policy::Mlfqs {
+        return;
+    }
+    let _priority = _priority.min(PRI_MAX);
+    current().set_priority(_priority);
     schedule();
     current().priority_setted.lock().replace(_priority);
 }
@@ -98,30 +111,35 @@ pub fn get_priority() -> u32 {
 
 /// (Lab1) Make the current thread sleep for the given ticks.
 pub fn sleep(ticks: i64) {
-    use crate::sbi::timer::timer_ticks;
-    use sbi::interrupt::set;
+    use crate::sbi::timer::{timer_ticks, CLOCK_PER_TICK};
 
-    // kprintln!("CALLED SLEEP {}", timer_ticks());
     if ticks <= 0 {
         return;
     }
+    sleep_until((timer_ticks() + ticks) as usize * CLOCK_PER_TICK);
+}
+
+/// Makes the current thread sleep for the given microseconds.
+pub fn sleep_us(us: usize) {
+    use crate::sbi::timer::{clock, us_to_clock};
+
+    if us == 0 {
+        return;
+    }
+    sleep_until(clock() + us_to_clock(us));
+}
+
+/// Blocks the current thread until the clock reads `clock`.
+fn sleep_until(clock: usize) {
+    use sbi::interrupt::set;
 
     let old = set(false);
-    let curren_tick = timer_ticks();
-    let current = current();
-    let wake_time = curren_tick + ticks;
-    SLEEP_QUEUE.lock().push(
-        (SleepData {
-            ticks: wake_time,
-            thread: current.clone(),
-        }),
-    );
-    // kprint!("CHECK ADDED {}", SLEEP_QUEUE.lock().len());
-    set(old);
+    SLEEP_QUEUE.push(SleepData {
+        clock,
+        thread: current(),
+    });
+    // The new sleeper may be due before the timer goes off.
+    sbi::timer::next();
     block();
-
-    /*
-    while timer_elapsed(start) < ticks {
-        schedule();
-    }*/
+    set(old);
 }
@@ -2,21 +2,30 @@
 
 use alloc::boxed::Box;
 use alloc::collections::vec_deque::VecDeque;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
 use core::arch::global_asm;
 use core::fmt::{self, Debug};
-use core::sync::atomic::{AtomicIsize, AtomicU32, Ordering::SeqCst};
+use core::sync::atomic::{
+    AtomicBool, AtomicI32, AtomicIsize, AtomicU32, AtomicU64, Ordering::SeqCst,
+};
 
 use crate::mem::{kalloc, kfree, PageTable, PG_SIZE};
 use crate::sbi::interrupt;
 use crate::sync::sleep::{self, DonationData};
-use crate::thread::Manager;
+use crate::sync::Semaphore;
+use crate::thread::join::{JoinHandle, Packet, Report};
+use crate::thread::scheduler::edf::Realtime;
+use crate::thread::scheduler::share::TICKETS_DEFAULT;
+use crate::thread::scheduler::Class;
+use crate::thread::stats::Counters;
+use crate::thread::{Manager, Schedule};
 use crate::userproc::UserProc;
 
 pub const PRI_DEFAULT: u32 = 31;
 pub const PRI_MAX: u32 = 63;
 pub const PRI_MIN: u32 = 0;
+/// Default stack size of kernel threads.
 pub const STACK_SIZE: usize = PG_SIZE * 4;
 pub const STACK_ALIGN: usize = 16;
 pub const STACK_TOP: usize = 0x80500000;
@@ -29,26 +38,59 @@ pub type Mutex<T> = crate::sync::Mutex<T, crate::sync::Intr>;
 #[repr(C)]
 pub struct Thread {
     tid: isize,
-    name: &'static str,
+    name: Arc<str>,
     stack: usize,
+    stack_size: usize,
     status: Mutex<Status>,
     context: Mutex<Context>,
     pub priority: AtomicU32,
     pub priority_setted: Mutex<Option<u32>>,
-    pub userproc: Option<UserProc>,
-    pub pagetable: Option<Mutex<PageTable>>,
+    /// Nice value, used by the MLFQS scheduler.
+    pub nice: AtomicI32,
+    /// Recent CPU time, as bits of a
+    /// [`Fixed`](crate::thread::scheduler::mlfqs::Fixed). Used by the MLFQS
+    /// scheduler.
+    pub recent_cpu: AtomicI32,
+    /// Share of the CPU, used by the lottery and stride schedulers.
+    pub tickets: AtomicU32,
+    /// Virtual time, used by the stride scheduler.
+    pub pass: AtomicU64,
+    /// Real-time parameters, if scheduled by EDF.
+    pub realtime: Option<Realtime>,
+    /// Scheduling class hint.
+    pub class: Class,
+    /// Harts the thread may run on, one bit each. Only recorded, as the
+    /// kernel runs on a single hart.
+    pub affinity: usize,
+    /// Whether the thread can't be joined.
+    detached: bool,
+    /// Where to report a panic, while the thread's
+    /// [`JoinHandle`] is alive.
+    pub(super) report: Option<Weak<dyn Report>>,
+    /// CPU accounting, read by [`Thread::stats`].
+    pub(super) counters: Counters,
+    /// Set once the thread is [`interrupt`](Self::interrupt)ed.
+    interrupted: AtomicBool,
+    /// Address of the semaphore the thread is blocked on, in an
+    /// [`interruptible`](Semaphore::down_interruptible) wait.
+    waiting_on: Mutex<Option<usize>>,
+    /// The user process, shared by all its threads.
+    pub userproc: Option<Arc<UserProc>>,
+    /// Shared by all threads of a user process.
+    pub pagetable: Option<Arc<Mutex<PageTable>>>,
     pub donationq: Mutex<VecDeque<DonationData>>,
     pub stored_prev: Mutex<(u32, u32)>,
 }
 
 impl Thread {
     pub fn new(
-        name: &'static str,
+        name: Arc<str>,
         stack: usize,
+        stack_size: usize,
         priority: u32,
         entry: usize,
-        userproc: Option<UserProc>,
-        pagetable: Option<PageTable>,
+        userproc: Option<Arc<UserProc>>,
+        pagetable: Option<Arc<Mutex<PageTable>>>,
     ) -> Self {
         /// The next thread's id
         static TID: AtomicIsize = AtomicIsize::new(0);
@@ -57,12 +99,25 @@ impl Thread {
             tid: TID.fetch_add(1, SeqCst),
             name,
             stack,
+            stack_size,
             status: Mutex::new(Status::Ready),
-            context: Mutex::new(Context::new(stack, entry)),
+            context: Mutex::new(Context::new(stack + stack_size, entry)),
             priority: AtomicU32::new(priority),
             userproc,
-            pagetable: pagetable.map(Mutex::new),
+            pagetable,
             priority_setted: Mutex::new(None),
+            nice: AtomicI32::new(0),
+            recent_cpu: AtomicI32::new(0),
+            tickets: AtomicU32::new(TICKETS_DEFAULT),
+            pass: AtomicU64::new(0),
+            realtime: None,
+            class: Class::default(),
+            affinity: usize::MAX,
+            detached: false,
+            report: None,
+            counters: Counters::new(),
+            interrupted: AtomicBool::new(false),
+            waiting_on: Mutex::new(None),
             donationq: Mutex::new(VecDeque::new()),
             stored_prev: Mutex::new((0xFFFFFFFF, 0)),
         }
@@ -97,15 +152,59 @@ impl Thread {
 
     pub fn donate(&self, acceptor: Arc<Thread>) {
         let don_priority = self.priority.load(SeqCst);
-        acceptor.priority.store(don_priority, SeqCst);
+        acceptor.set_priority(don_priority);
+        acceptor.counters.donated();
+    }
+
+    /// Sets the effective priority, and moves the thread to its new level
+    /// if it's waiting in the scheduler. Priorities above [`PRI_MAX`] are
+    /// taken as [`PRI_MAX`].
+    pub fn set_priority(&self, priority: u32) {
+        let priority = priority.min(PRI_MAX);
+        let mut scheduler = Manager::get().scheduler.lock();
+        let old = self.priority.swap(priority, SeqCst);
+        if old != priority && self.status() == Status::Ready {
+            scheduler.reprioritize(self, old);
+        }
+    }
+
+    /// Cuts short the [`interruptible`](Semaphore::down_interruptible)
+    /// wait the thread is blocked in, if any. Its later interruptible waits
+    /// return at once.
+    pub fn interrupt(self: &Arc<Self>) {
+        let old = interrupt::set(false);
+        self.interrupted.store(true, SeqCst);
+        let sema = self.waiting_on.lock().take();
+        if let Some(sema) = sema {
+            // The semaphore outlives the wait, which is still on.
+            unsafe { &*(sema as *const Semaphore) }.remove_waiter(self);
+        }
+        interrupt::set(old);
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.interrupted.load(SeqCst)
+    }
+
+    /// Records the semaphore the thread is about to block on, or clears it.
+    pub(crate) fn set_waiting_on(&self, sema: Option<&Semaphore>) {
+        *self.waiting_on.lock() = sema.map(|s| s as *const Semaphore as usize);
     }
 
     pub fn id(&self) -> isize {
         self.tid
     }
 
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stack_size(&self) -> usize {
+        self.stack_size
+    }
+
+    pub fn detached(&self) -> bool {
+        self.detached
     }
 
     pub fn status(&self) -> Status {
@@ -141,73 +240,180 @@ impl Drop for Thread {
         #[cfg(feature = "debug")]
         kprintln!("[THREAD] {:?}'s resources are released", self);
 
-        kfree(self.stack as *mut _, STACK_SIZE, STACK_ALIGN);
-        if let Some(pt) = &self.pagetable {
+        kfree(self.stack as *mut _, self.stack_size, STACK_ALIGN);
+        // The last thread of a process frees its pagetable.
+        if let Some(pt) = self
+            .pagetable
+            .take()
+            .and_then(|pt| Arc::try_unwrap(pt).ok())
+        {
             unsafe { pt.lock().destroy() };
         }
     }
 }
 
 /* --------------------------------- BUILDER -------------------------------- */
-pub struct Builder {
+pub struct Builder<T> {
     priority: u32,
-    name: &'static str,
+    name: Arc<str>,
+    stack_size: usize,
+    class: Class,
+    affinity: usize,
+    detached: bool,
     function: usize,
-    userproc: Option<UserProc>,
-    pagetable: Option<PageTable>,
+    userproc: Option<Arc<UserProc>>,
+    pagetable: Option<Arc<Mutex<PageTable>>>,
+    period: Option<i64>,
+    budget: Option<i64>,
+    deadline: Option<i64>,
+    packet: Arc<Packet<T>>,
 }
 
-impl Builder {
+impl<T: Send + 'static> Builder<T> {
     pub fn new<F>(function: F) -> Self
     where
-        F: FnOnce() + Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
     {
+        let packet = Arc::new(Packet::new());
+
+        // Nobody wants the result once the handle is dropped.
+        let weak = Arc::downgrade(&packet);
+        let function = move || {
+            let result = function();
+            if let Some(packet) = weak.upgrade() {
+                packet.finish(Ok(result));
+            }
+        };
+
         // `*mut dyn FnOnce()` is a fat pointer, box it again to ensure FFI-safety.
         let function: *mut Box<dyn FnOnce()> = Box::into_raw(Box::new(Box::new(function)));
 
         Self {
             priority: PRI_DEFAULT,
-            name: "Default",
+            name: Arc::from("Default"),
+            stack_size: STACK_SIZE,
+            class: Class::default(),
+            affinity: usize::MAX,
+            detached: false,
             function: function as usize,
             userproc: None,
             pagetable: None,
+            period: None,
+            budget: None,
+            deadline: None,
+            packet,
         }
     }
 
+    /// Priorities above [`PRI_MAX`] are taken as [`PRI_MAX`].
     pub fn priority(mut self, priority: u32) -> Self {
-        self.priority = priority;
+        self.priority = priority.min(PRI_MAX);
         self
     }
 
-    pub fn name(mut self, name: &'static str) -> Self {
-        self.name = name;
+    /// Takes a `&str`, a `String`, or an `Arc<str>` to share one name.
+    pub fn name<S: Into<Arc<str>>>(mut self, name: S) -> Self {
+        self.name = name.into();
         self
     }
 
-    pub fn pagetable(mut self, pagetable: PageTable) -> Self {
+    /// Size of the kernel stack, rounded up to pages. [`STACK_SIZE`] by
+    /// default.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "empty stack");
+        self.stack_size = (size + PG_SIZE - 1) / PG_SIZE * PG_SIZE;
+        self
+    }
+
+    /// Hints the scheduler about how the thread runs.
+    pub fn class(mut self, class: Class) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Harts the thread may run on, one bit each. All of them by default.
+    pub fn affinity(mut self, mask: usize) -> Self {
+        assert_ne!(mask, 0, "no hart to run on");
+        self.affinity = mask;
+        self
+    }
+
+    /// Makes the thread unjoinable. Its result is dropped, and a panic in it
+    /// shuts down the kernel.
+    pub fn detached(mut self) -> Self {
+        self.detached = true;
+        self
+    }
+
+    pub fn pagetable(mut self, pagetable: Arc<Mutex<PageTable>>) -> Self {
         self.pagetable = Some(pagetable);
         self
     }
 
-    pub fn userproc(mut self, userproc: UserProc) -> Self {
+    pub fn userproc(mut self, userproc: Arc<UserProc>) -> Self {
         self.userproc = Some(userproc);
         self
     }
 
+    /// Makes the thread real-time, running a job every `period` ticks. See
+    /// [`edf`](crate::thread::scheduler::edf).
+    pub fn period(mut self, period: i64) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// CPU ticks each job may use, the whole period by default.
+    pub fn budget(mut self, budget: i64) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Ticks after its release each job should be done in, the whole period
+    /// by default.
+    pub fn deadline(mut self, deadline: i64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
     pub fn build(self) -> Arc<Thread> {
-        let stack = kalloc(STACK_SIZE, STACK_ALIGN) as usize;
+        let realtime = match self.period {
+            Some(period) => Some(Realtime::new(
+                period,
+                self.budget.unwrap_or(period),
+                self.deadline.unwrap_or(period),
+            )),
+            None => {
+                assert!(
+                    self.budget.is_none() && self.deadline.is_none(),
+                    "budget or deadline without period"
+                );
+                None
+            }
+        };
+
+        let stack = kalloc(self.stack_size, STACK_ALIGN) as usize;
 
         // Put magic number at the bottom of the stack.
         unsafe { (stack as *mut usize).write(MAGIC) };
 
-        Arc::new(Thread::new(
+        let mut thread = Thread::new(
             self.name,
             stack,
+            self.stack_size,
             self.priority,
             self.function,
             self.userproc,
             self.pagetable,
-        ))
+        );
+        thread.realtime = realtime;
+        thread.class = self.class;
+        thread.affinity = self.affinity;
+        thread.detached = self.detached;
+        if !self.detached {
+            let report: Arc<dyn Report> = self.packet;
+            thread.report = Some(Arc::downgrade(&report));
+        }
+        Arc::new(thread)
     }
 
     /// Spawns a kernel thread and registers it to the [`Manager`].
@@ -215,7 +421,9 @@ impl Builder {
     /// `userproc` and `pagetable` have to be set properly.
     ///
     /// Note that this function CANNOT be called during [`Manager`]'s initialization.
-    pub fn spawn(self) -> Arc<Thread> {
+    pub fn spawn(self) -> JoinHandle<T> {
+        // Without the packet, a detached thread's result is dropped.
+        let packet = (!self.detached).then(|| self.packet.clone());
         let new_thread = self.build();
 
         // #[cfg(feature = "debug")]
@@ -225,7 +433,7 @@ impl Builder {
         Manager::get().schedule();
 
         // Off you go
-        new_thread
+        JoinHandle::new(new_thread, packet)
     }
 }
 
@@ -258,11 +466,10 @@ pub struct Context {
 }
 
 impl Context {
-    fn new(stack: usize, entry: usize) -> Self {
+    fn new(stack_top: usize, entry: usize) -> Self {
         Self {
             ra: kernel_thread_entry as usize,
-            // calculate the address of stack top
-            sp: stack + STACK_SIZE,
+            sp: stack_top,
             // s0 stores a thread's entry point. For a new thread,
             // s0 will then be used as the first argument of `kernel_thread`.
             s: core::array::from_fn(|i| if i == 0 { entry } else { 0 }),
@@ -0,0 +1,117 @@
+//! Join handles
+//!
+//! [`Builder::spawn`](crate::thread::Builder::spawn) returns a [`JoinHandle`],
+//! whose [`join`](JoinHandle::join) waits for the thread to finish and takes
+//! the return value of its function.
+//!
+//! A thread that panics while its handle is alive exits, and the panic
+//! reaches [`join`](JoinHandle::join) as [`OsError::Panicked`]. State it
+//! shares through an `Arc` is left as the panic found it, maybe halfway
+//! updated, and sleep locks it held stay held. A panic shuts down the
+//! kernel instead if the thread has no handle, holds a lock of the thread
+//! [`Manager`], or gives or takes a priority donation.
+//!
+//! A [`detached`](crate::thread::Builder::detached) thread has a handle that
+//! can't join it.
+//!
+
+use alloc::sync::{Arc, Weak};
+use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use crate::sync::Semaphore;
+use crate::thread::{self, Manager, Mutex, Thread};
+use crate::{OsError, Result};
+
+/// Where a thread puts how it ended, shared with its [`JoinHandle`].
+pub(super) struct Packet<T> {
+    result: Mutex<Option<Result<T>>>,
+    done: Semaphore,
+}
+
+impl<T> Packet<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            result: Mutex::new(None),
+            done: Semaphore::new(0),
+        }
+    }
+
+    pub(super) fn finish(&self, result: Result<T>) {
+        self.result.lock().replace(result);
+        self.done.up();
+    }
+}
+
+/// A [`Packet`] of any type, to report panics to.
+pub(super) trait Report: Send + Sync {
+    fn panicked(&self);
+}
+
+impl<T: Send> Report for Packet<T> {
+    fn panicked(&self) {
+        self.finish(Err(OsError::Panicked));
+    }
+}
+
+/// An owned permission to join on a thread.
+pub struct JoinHandle<T> {
+    thread: Arc<Thread>,
+    /// `None` if the thread is detached.
+    packet: Option<Arc<Packet<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub(super) fn new(thread: Arc<Thread>, packet: Option<Arc<Packet<T>>>) -> Self {
+        Self { thread, packet }
+    }
+
+    pub fn thread(&self) -> &Arc<Thread> {
+        &self.thread
+    }
+
+    /// Waits for the thread to finish, and returns what its function
+    /// returned.
+    ///
+    /// ## Errors
+    /// - [`OsError::Panicked`]: The thread panicked.
+    /// - [`OsError::Detached`]: The thread is detached.
+    pub fn join(self) -> Result<T> {
+        let packet = self.packet.ok_or(OsError::Detached)?;
+        packet.done.down();
+        let result = packet.result.lock().take();
+        result.unwrap()
+    }
+}
+
+/// Called on a panic. If the current thread can be joined, reports the
+/// panic to its joiner and exits. Returns otherwise, or on a panic while
+/// doing so.
+pub fn exit_on_panic() {
+    /// Set while reporting a panic.
+    static PANICKING: AtomicBool = AtomicBool::new(false);
+
+    if PANICKING.swap(true, SeqCst) {
+        return;
+    }
+
+    // Exiting needs the manager's locks, and threads in a donation with the
+    // current one would keep its priority or wait for it forever.
+    if Manager::get().is_locked() {
+        return;
+    }
+    let current = thread::current();
+    if current.donationq.is_locked() || !current.donationq.lock().is_empty() {
+        return;
+    }
+
+    let report = match current.report.as_ref().and_then(Weak::upgrade) {
+        Some(report) => report,
+        None => return,
+    };
+    drop(current);
+    report.panicked();
+    drop(report);
+
+    PANICKING.store(false, SeqCst);
+    thread::exit()
+}
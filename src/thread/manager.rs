@@ -6,8 +6,8 @@ use core::mem;
 use core::ops::DerefMut;
 
 use crate::bootstack;
-use crate::mem::KernelPgTable;
-use crate::sbi::interrupt;
+use crate::mem::{KernelPgTable, PG_SIZE};
+use crate::sbi::{interrupt, timer};
 use crate::sync::Lazy;
 use crate::thread::{
     schedule, switch, Builder, Mutex, Schedule, Scheduler, Status, Thread, MAGIC, PRI_DEFAULT,
@@ -23,6 +23,8 @@ pub struct Manager {
     pub current: Mutex<Arc<Thread>>,
     /// All alive and not yet destroyed threads
     all: Mutex<Vec<Arc<Thread>>>,
+    /// The thread running when no other thread is ready
+    idle: Arc<Thread>,
 }
 
 impl Manager {
@@ -30,8 +32,10 @@ impl Manager {
         static TMANAGER: Lazy<Manager> = Lazy::new(|| {
             // Manully create initial thread.
             let initial = Arc::new(Thread::new(
-                "Initial",
+                Arc::from("Initial"),
                 bootstack as usize,
+                // As reserved in `boot.rs`.
+                PG_SIZE * 8,
                 PRI_DEFAULT,
                 0,
                 None,
@@ -40,18 +44,26 @@ impl Manager {
             unsafe { (bootstack as *mut usize).write(MAGIC) };
             initial.set_status(Status::Running);
 
-            let manager = Manager {
-                scheduler: Mutex::new(Scheduler::default()),
-                all: Mutex::new(Vec::from([initial.clone()])),
-                current: Mutex::new(initial),
-            };
-
+            // Waits for interrupts when nothing else is ready, with the timer
+            // set only for pending events. Interrupts stay off from the check
+            // for ready threads to `wfi`, which still wakes up on a pending one.
             let idle = Builder::new(|| loop {
-                schedule()
+                let old = interrupt::set(false);
+                schedule();
+                timer::next();
+                unsafe { riscv::asm::wfi() };
+                interrupt::set(old);
             })
             .name("Idle")
             .priority(PRI_MIN)
             .build();
+
+            let manager = Manager {
+                scheduler: Mutex::new(Scheduler::new()),
+                all: Mutex::new(Vec::from([initial.clone()])),
+                current: Mutex::new(initial),
+                idle: idle.clone(),
+            };
             manager.register(idle);
 
             manager
@@ -60,6 +72,22 @@ impl Manager {
         &TMANAGER
     }
 
+    /// All alive and not yet destroyed threads, in order of creation.
+    pub fn all(&self) -> Vec<Arc<Thread>> {
+        self.all.lock().clone()
+    }
+
+    /// Whether any lock of the manager is held, e.g. by a thread that
+    /// panicked while scheduling.
+    pub fn is_locked(&self) -> bool {
+        self.scheduler.is_locked() || self.current.is_locked() || self.all.is_locked()
+    }
+
+    /// The idle thread.
+    pub fn idle(&self) -> &Arc<Thread> {
+        &self.idle
+    }
+
     pub(super) fn register(&self, thread: Arc<Thread>) {
         // Register it into the scheduler
         self.scheduler.lock().register(thread.clone());
@@ -103,6 +131,7 @@ impl Manager {
             assert_eq!(next.status(), Status::Ready);
             assert!(!next.overflow(), "Next thread has overflowed its stack.");
             next.set_status(Status::Running);
+            next.counters.switch_in(timer::clock());
 
             // Update the current thread to the next running thread
             let previous = mem::replace(self.current.lock().deref_mut(), next);
@@ -141,7 +170,17 @@ impl Manager {
         // #[cfg(feature = "debug")]
         kprintln!("[THREAD] switch to {:?}", *self.current.lock());
 
-        match previous.status() {
+        // The idle thread may have stopped the tick.
+        if Arc::ptr_eq(&previous, &self.idle) {
+            timer::next();
+        }
+
+        let status = previous.status();
+        previous
+            .counters
+            .switch_out(timer::clock(), status == Status::Running);
+
+        match status {
             Status::Dying => {
                 // A thread's resources should be released at this point
                 self.all.lock().retain(|t| t.id() != previous.id());
@@ -2,24 +2,34 @@
 //!
 //! [`Manager`](crate::thread::Manager) relies on scheduler to support Kernel Thread Scheduling.
 //! FCFS is an example implementation of a scheduler, you can add new schedulers by implementing
-//! [`Schedule`] trait.
+//! [`Schedule`] trait, and a [`Policy`] for it.
+//!
+//! The policy is picked at boot by bootarg `sched=fcfs|priority|mlfqs|lottery|stride`, and may
+//! be switched at runtime by [`set_policy`]. Without the bootarg, feature
+//! `thread-scheduler-priority` or `thread-scheduler-mlfqs` picks the policy, MLFQS if both are
+//! on, and FCFS if neither is.
+//!
+//! Real-time threads are scheduled apart by [`edf::Edf`], which takes precedence over the
+//! policy. [`Scheduler`] puts both together.
 //!
 
+pub mod edf;
 pub mod fcfs;
+pub mod mlfqs;
 pub mod priority;
+pub mod share;
 
+use alloc::boxed::Box;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU8, Ordering::SeqCst};
 
-use crate::thread::Thread;
-
-#[cfg(feature = "thread-scheduler-priority")]
-// (Lab1) Your task: priority scheduling
-pub type Scheduler = self::priority::PriorityScheduler;
-#[cfg(not(feature = "thread-scheduler-priority"))]
-pub type Scheduler = self::fcfs::Fcfs;
+use crate::thread::{current, Manager, Status, Thread};
+use crate::OsError;
 
 /// Basic functionalities of thread schedulers
-pub trait Schedule: Default {
+pub trait Schedule: Send {
     /// Notify the scheduler that a thread is able to run. Then, this thread
     /// becomes a candidate of [`schedule`](Schedule::schedule).
     fn register(&mut self, thread: Arc<Thread>);
@@ -27,4 +37,146 @@ pub trait Schedule: Default {
     /// Choose the next thread to run. `None` if scheduler decides to keep running
     /// the current thread.
     fn schedule(&mut self) -> Option<Arc<Thread>>;
+
+    /// Notify the scheduler that the priority of a registered `thread` changed
+    /// from `old`. Schedulers that don't keep threads by priority ignore it.
+    fn reprioritize(&mut self, _thread: &Thread, _old: u32) {}
+
+    /// Removes all registered threads, roughly in the order they would run.
+    fn drain(&mut self) -> Vec<Arc<Thread>>;
+}
+
+/// The scheduler of [`Manager`]: real-time threads by EDF, then the others
+/// by the current policy.
+pub struct Scheduler {
+    realtime: edf::Edf,
+    normal: Box<dyn Schedule>,
+}
+
+impl Scheduler {
+    pub(super) fn new() -> Self {
+        Self {
+            realtime: edf::Edf::default(),
+            normal: policy().scheduler(),
+        }
+    }
+}
+
+impl Schedule for Scheduler {
+    fn register(&mut self, thread: Arc<Thread>) {
+        match thread.realtime {
+            Some(_) => self.realtime.register(thread),
+            None => self.normal.register(thread),
+        }
+    }
+
+    fn schedule(&mut self) -> Option<Arc<Thread>> {
+        if let Some(next) = self.realtime.schedule() {
+            return Some(next);
+        }
+
+        // A running real-time thread is not preempted by normal ones.
+        let current = current();
+        if current.status() == Status::Running && current.realtime.is_some() {
+            return None;
+        }
+        self.normal.schedule()
+    }
+
+    fn reprioritize(&mut self, thread: &Thread, old: u32) {
+        if thread.realtime.is_none() {
+            self.normal.reprioritize(thread, old);
+        }
+    }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        let mut threads = self.realtime.drain();
+        threads.append(&mut self.normal.drain());
+        threads
+    }
+}
+
+/// How a thread runs, as a hint to schedulers. None of them acts on it yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Class {
+    /// Mostly waits for input, and should respond quickly.
+    Interactive,
+    #[default]
+    Normal,
+    /// Runs long on the CPU, and may wait.
+    Batch,
+}
+
+/// Scheduling policies, each backed by a [`Schedule`] implementation.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    Fcfs,
+    Priority,
+    Mlfqs,
+    Lottery,
+    Stride,
+}
+
+impl Policy {
+    /// The policy picked by cargo features.
+    pub const DEFAULT: Self = if cfg!(feature = "thread-scheduler-mlfqs") {
+        Self::Mlfqs
+    } else if cfg!(feature = "thread-scheduler-priority") {
+        Self::Priority
+    } else {
+        Self::Fcfs
+    };
+
+    /// Creates an empty scheduler of this policy.
+    pub(super) fn scheduler(self) -> Box<dyn Schedule> {
+        match self {
+            Self::Fcfs => Box::<fcfs::Fcfs>::default(),
+            Self::Priority => Box::<priority::PriorityScheduler>::default(),
+            Self::Mlfqs => Box::<mlfqs::Mlfqs>::default(),
+            Self::Lottery => Box::<share::Lottery>::default(),
+            Self::Stride => Box::<share::Stride>::default(),
+        }
+    }
+}
+
+impl FromStr for Policy {
+    type Err = OsError;
+
+    fn from_str(name: &str) -> Result<Self, OsError> {
+        match name {
+            "fcfs" => Ok(Self::Fcfs),
+            "priority" => Ok(Self::Priority),
+            "mlfqs" => Ok(Self::Mlfqs),
+            "lottery" => Ok(Self::Lottery),
+            "stride" => Ok(Self::Stride),
+            _ => Err(OsError::UserError),
+        }
+    }
+}
+
+/// The current policy, as a `u8`.
+static POLICY: AtomicU8 = AtomicU8::new(Policy::DEFAULT as u8);
+
+/// Returns the current scheduling policy.
+pub fn policy() -> Policy {
+    match POLICY.load(SeqCst) {
+        0 => Policy::Fcfs,
+        1 => Policy::Priority,
+        2 => Policy::Mlfqs,
+        3 => Policy::Lottery,
+        _ => Policy::Stride,
+    }
+}
+
+/// Switches to `policy`. Ready threads are moved to the new scheduler.
+pub fn set_policy(policy: Policy) {
+    let mut scheduler = Manager::get().scheduler.lock();
+    let ready = scheduler.normal.drain();
+
+    scheduler.normal = policy.scheduler();
+    POLICY.store(policy as u8, SeqCst);
+    ready
+        .into_iter()
+        .for_each(|thread| scheduler.normal.register(thread));
 }
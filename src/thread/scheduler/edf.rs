@@ -0,0 +1,187 @@
+//! Earliest-deadline-first real-time class.
+//!
+//! A real-time thread runs a job every `period` ticks. Each job may use
+//! up to `budget` ticks of CPU, and should be done within `deadline` ticks
+//! of its release. Ready real-time threads take precedence over the normal
+//! scheduler, and among them the one with the earliest deadline runs.
+//!
+//! On every timer tick, [`tick`]:
+//! - charges the running real-time thread a tick of its budget, and blocks
+//!   it until the next release once the budget is used up;
+//! - counts a deadline miss if a job is not done at its deadline;
+//! - releases a new job at the start of each period, waking the thread if
+//!   it waits. A job still running then carries on as the new job.
+//!
+//! A thread marks its job done by [`wait_next_period`].
+//!
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
+
+use crate::sbi::{interrupt, timer::timer_ticks};
+use crate::thread::{self, current, Manager, Mutex, Schedule, Status, Thread};
+
+/// Number of alive real-time threads.
+static REALTIME: AtomicUsize = AtomicUsize::new(0);
+
+/// Real-time parameters and state of a thread, in ticks.
+pub struct Realtime {
+    pub period: i64,
+    pub budget: i64,
+    pub deadline: i64,
+    job: Mutex<Job>,
+    misses: AtomicU64,
+}
+
+/// The current job of a real-time thread.
+struct Job {
+    release: i64,
+    /// Absolute deadline.
+    deadline: i64,
+    /// CPU ticks used.
+    used: i64,
+    done: bool,
+    /// Blocked until the next release.
+    waiting: bool,
+}
+
+impl Realtime {
+    /// Parameters of a thread whose first job is released now.
+    ///
+    /// ## Panics
+    /// Unless `0 < budget <= deadline <= period`.
+    pub fn new(period: i64, budget: i64, deadline: i64) -> Self {
+        assert!(0 < budget && budget <= deadline && deadline <= period);
+
+        let release = timer_ticks();
+        REALTIME.fetch_add(1, SeqCst);
+        Self {
+            period,
+            budget,
+            deadline,
+            job: Mutex::new(Job {
+                release,
+                deadline: release + deadline,
+                used: 0,
+                done: false,
+                waiting: false,
+            }),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of jobs not done by their deadlines.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(SeqCst)
+    }
+
+    fn deadline(&self) -> i64 {
+        self.job.lock().deadline
+    }
+}
+
+/// Whether there are real-time threads.
+pub fn active() -> bool {
+    REALTIME.load(SeqCst) != 0
+}
+
+impl Drop for Realtime {
+    fn drop(&mut self) {
+        REALTIME.fetch_sub(1, SeqCst);
+    }
+}
+
+/// EDF scheduler of the ready real-time threads.
+#[derive(Default)]
+pub struct Edf(Vec<Arc<Thread>>);
+
+impl Schedule for Edf {
+    fn register(&mut self, thread: Arc<Thread>) {
+        assert!(thread.realtime.is_some());
+        self.0.push(thread)
+    }
+
+    fn schedule(&mut self) -> Option<Arc<Thread>> {
+        let deadline = |t: &Thread| t.realtime.as_ref().unwrap().deadline();
+
+        // The first of the ready threads with the earliest deadline.
+        let (idx, next) = self
+            .0
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, t)| (deadline(t), *i))?;
+
+        let current = current();
+        if current.status() == Status::Running
+            && current.realtime.is_some()
+            && deadline(&current) <= deadline(next)
+        {
+            return None;
+        }
+        Some(self.0.remove(idx))
+    }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        self.0.drain(..).collect()
+    }
+}
+
+/// Charges, releases and checks the deadlines of real-time threads on the
+/// `now`th timer tick. Called with interrupts off.
+pub fn tick(now: i64) {
+    if !active() {
+        return;
+    }
+
+    let current = current();
+    for thread in Manager::get().all() {
+        let rt = match thread.realtime.as_ref() {
+            Some(rt) => rt,
+            None => continue,
+        };
+        let mut job = rt.job.lock();
+
+        if Arc::ptr_eq(&thread, &current) && thread.status() == Status::Running {
+            job.used += 1;
+            if job.used >= rt.budget && !job.done {
+                job.waiting = true;
+                thread.set_status(Status::Blocked);
+            }
+        }
+
+        if now == job.deadline && !job.done {
+            rt.misses.fetch_add(1, SeqCst);
+            kprintln!("[EDF] {:?} missed its deadline at tick {}", thread, now);
+        }
+
+        if now >= job.release + rt.period {
+            job.release += rt.period;
+            job.deadline = job.release + rt.deadline;
+            job.used = 0;
+            job.done = false;
+            if job.waiting {
+                job.waiting = false;
+                drop(job);
+                thread::wake_up(thread);
+            }
+        }
+    }
+}
+
+/// Marks the job of the current real-time thread done, and blocks until
+/// the next one is released.
+///
+/// ## Panics
+/// If the current thread is not real-time.
+pub fn wait_next_period() {
+    let old = interrupt::set(false);
+    {
+        let current = current();
+        let mut job = current.realtime.as_ref().expect("not real-time").job.lock();
+        job.done = true;
+        job.waiting = true;
+    }
+    thread::block();
+    interrupt::set(old);
+}
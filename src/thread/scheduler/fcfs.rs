@@ -1,5 +1,6 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use crate::thread::{Schedule, Thread};
 
@@ -15,4 +16,8 @@ impl Schedule for Fcfs {
     fn schedule(&mut self) -> Option<Arc<Thread>> {
         self.0.pop_back()
     }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        self.0.drain(..).rev().collect()
+    }
 }
@@ -0,0 +1,222 @@
+//! Multi-level feedback queue scheduler, as in 4.4BSD.
+//!
+//! Threads don't set their priorities. Instead, priorities are computed
+//! from how much CPU time a thread got recently, and how nice it is to
+//! others, on timer ticks:
+//!
+//! - every tick, `recent_cpu` of the running thread grows by 1;
+//! - every second, the system `load_avg` follows the number of ready
+//!   threads, and `recent_cpu` of every thread decays:
+//!   - `load_avg = 59/60 * load_avg + 1/60 * ready_threads`,
+//!   - `recent_cpu = 2 * load_avg / (2 * load_avg + 1) * recent_cpu + nice`;
+//! - every 4th tick, `priority = PRI_MAX - recent_cpu / 4 - nice * 2`.
+//!
+//! Fractional values are [`Fixed`]. The idle thread is left out, and stays
+//! at [`PRI_MIN`].
+//!
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::ops::{Add, Div, Mul};
+use core::sync::atomic::{AtomicI32, Ordering::SeqCst};
+
+use super::{policy, Policy};
+use crate::sbi::timer::TICKS_PER_SEC;
+use crate::thread::{current, Manager, Schedule, Status, Thread, PRI_MAX, PRI_MIN};
+
+pub const NICE_MIN: i32 = -20;
+pub const NICE_MAX: i32 = 20;
+
+/// Ticks between two priority updates.
+const PRIORITY_PERIOD: i64 = 4;
+
+/// A 17.14 fixed-point number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    const ONE: i32 = 1 << 14;
+
+    pub const fn from_int(n: i32) -> Self {
+        Self(n * Self::ONE)
+    }
+
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// Rounds toward zero.
+    pub const fn trunc(self) -> i32 {
+        self.0 / Self::ONE
+    }
+
+    /// Rounds to the nearest integer.
+    pub const fn round(self) -> i32 {
+        if self.0 >= 0 {
+            (self.0 + Self::ONE / 2) / Self::ONE
+        } else {
+            (self.0 - Self::ONE / 2) / Self::ONE
+        }
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self((self.0 as i64 * rhs.0 as i64 / Self::ONE as i64) as i32)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Self((self.0 as i64 * Self::ONE as i64 / rhs.0 as i64) as i32)
+    }
+}
+
+impl Mul<i32> for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Div<i32> for Fixed {
+    type Output = Self;
+
+    fn div(self, rhs: i32) -> Self {
+        Self(self.0 / rhs)
+    }
+}
+
+/// The system load average, as bits of a [`Fixed`].
+static LOAD_AVG: AtomicI32 = AtomicI32::new(0);
+
+/// MLFQS scheduler. Ready threads of the same priority run in turns.
+#[derive(Default)]
+pub struct Mlfqs(VecDeque<Arc<Thread>>);
+
+impl Schedule for Mlfqs {
+    fn register(&mut self, thread: Arc<Thread>) {
+        self.0.push_back(thread)
+    }
+
+    fn schedule(&mut self) -> Option<Arc<Thread>> {
+        // The first of the ready threads with the highest priority.
+        let (idx, next) = self
+            .0
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, t)| (t.priority.load(SeqCst), Reverse(*i)))?;
+
+        let current = current();
+        if current.status() == Status::Running
+            && next.priority.load(SeqCst) < current.priority.load(SeqCst)
+        {
+            return None;
+        }
+        self.0.remove(idx)
+    }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        self.0.drain(..).collect()
+    }
+}
+
+fn recent_cpu(thread: &Thread) -> Fixed {
+    Fixed::from_bits(thread.recent_cpu.load(SeqCst))
+}
+
+fn load_avg() -> Fixed {
+    Fixed::from_bits(LOAD_AVG.load(SeqCst))
+}
+
+/// Computes the priority of `thread` from its `recent_cpu` and nice value.
+fn update_priority(thread: &Thread) {
+    let priority = PRI_MAX as i32 - (recent_cpu(thread) / 4).trunc() - thread.nice.load(SeqCst) * 2;
+    let priority = priority.clamp(PRI_MIN as i32, PRI_MAX as i32);
+    thread.priority.store(priority as u32, SeqCst);
+}
+
+/// Updates the statistics on the `ticks`th timer tick, if the policy is
+/// MLFQS. Called with interrupts off.
+pub fn tick(ticks: i64) {
+    if policy() != Policy::Mlfqs {
+        return;
+    }
+
+    let manager = Manager::get();
+    let current = manager.current.lock().clone();
+    let is_idle = |thread: &Arc<Thread>| Arc::ptr_eq(thread, manager.idle());
+
+    if !is_idle(&current) {
+        let cpu = recent_cpu(&current) + Fixed::from_int(1);
+        current.recent_cpu.store(cpu.to_bits(), SeqCst);
+    }
+
+    if ticks % TICKS_PER_SEC as i64 == 0 {
+        let all = manager.all();
+        let ready = all
+            .iter()
+            .filter(|t| !is_idle(t) && matches!(t.status(), Status::Ready | Status::Running))
+            .count() as i32;
+        let load = (load_avg() * 59 + Fixed::from_int(ready)) / 60;
+        LOAD_AVG.store(load.to_bits(), SeqCst);
+
+        let decay = load * 2 / (load * 2 + Fixed::from_int(1));
+        for thread in all.iter().filter(|t| !is_idle(t)) {
+            let cpu = decay * recent_cpu(thread) + Fixed::from_int(thread.nice.load(SeqCst));
+            thread.recent_cpu.store(cpu.to_bits(), SeqCst);
+        }
+    }
+
+    if ticks % PRIORITY_PERIOD == 0 {
+        for thread in manager.all().iter().filter(|t| !is_idle(t)) {
+            update_priority(thread);
+        }
+    }
+}
+
+/// Sets the nice value of the current thread, clamped to
+/// `[NICE_MIN, NICE_MAX]`, and yields if it's no longer the highest
+/// priority thread.
+pub fn set_nice(nice: i32) {
+    let current = current();
+    current.nice.store(nice.clamp(NICE_MIN, NICE_MAX), SeqCst);
+    update_priority(&current);
+    drop(current);
+
+    crate::thread::schedule();
+}
+
+/// Returns the nice value of the current thread.
+pub fn get_nice() -> i32 {
+    current().nice.load(SeqCst)
+}
+
+/// Returns 100 times the `recent_cpu` of the current thread, rounded.
+pub fn get_recent_cpu() -> i32 {
+    (recent_cpu(&current()) * 100).round()
+}
+
+/// Returns 100 times the system load average, rounded.
+pub fn get_load_avg() -> i32 {
+    (load_avg() * 100).round()
+}
@@ -1,92 +1,95 @@
-use core::{cmp::Reverse, sync::atomic::Ordering};
+//! Priority scheduler.
+//!
+//! Ready threads wait in one queue per priority level, and a bitmap records
+//! which levels are non-empty, so both [`register`](Schedule::register) and
+//! [`schedule`](Schedule::schedule) take constant time. A waiting thread
+//! whose priority changes, e.g. by donation, is moved to its new level by
+//! [`reprioritize`](Schedule::reprioritize).
+//!
 
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
-use fdt::standard_nodes::Chosen;
-
-use crate::thread::{current, scheduler::priority, Schedule, Status, Thread};
+use alloc::vec::Vec;
 use core::array;
+use core::ptr;
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::thread::{current, Schedule, Status, Thread, PRI_MAX};
+
+const LEVELS: usize = PRI_MAX as usize + 1;
 
-/// Priority scheduler.
-// #[derive(Default)]
-pub struct PriorityScheduler([VecDeque<Arc<Thread>>; 64]);
+/// Priority scheduler. Ready threads of the same priority run in turns.
+pub struct PriorityScheduler {
+    queues: [VecDeque<Arc<Thread>>; LEVELS],
+    /// Bit `i` is set iff `queues[i]` is non-empty.
+    ready: u64,
+}
 
 impl Default for PriorityScheduler {
     fn default() -> Self {
-        PriorityScheduler(array::from_fn(|_| VecDeque::new()))
+        PriorityScheduler {
+            queues: array::from_fn(|_| VecDeque::new()),
+            ready: 0,
+        }
+    }
+}
+
+impl PriorityScheduler {
+    /// The highest non-empty level.
+    fn highest(&self) -> Option<usize> {
+        match self.ready {
+            0 => None,
+            ready => Some(LEVELS - 1 - ready.leading_zeros() as usize),
+        }
+    }
+
+    fn push(&mut self, level: usize, thread: Arc<Thread>) {
+        self.queues[level].push_front(thread);
+        self.ready |= 1 << level;
+    }
+
+    fn pop(&mut self, level: usize) -> Option<Arc<Thread>> {
+        let thread = self.queues[level].pop_back();
+        if self.queues[level].is_empty() {
+            self.ready &= !(1 << level);
+        }
+        thread
     }
 }
 
 impl Schedule for PriorityScheduler {
     fn register(&mut self, thread: Arc<Thread>) {
-        /*kprintln!(
-            "registed thread, id {}, name {}, at priority {}",
-            thread.id(),
-            thread.name(),
-            thread.priority.load(Ordering::SeqCst)
-        );*/
-        self.0[thread.priority.load(Ordering::SeqCst) as usize].push_front(thread);
+        let level = thread.priority.load(SeqCst) as usize;
+        self.push(level, thread);
     }
 
     fn schedule(&mut self) -> Option<Arc<Thread>> {
-        self.update_priority();
-        let mut called: bool = false;
-        for i in (0..64) {
-            if !(self.0)[i].is_empty() {
-                for j in &self.0[i] {
-                    // kprint!("({} at {}) ", j.id(), i);
-                }
-                called = true;
-            }
-        }
-        if (called) {
-            // kprintln!(" <- available");
-        }
+        let level = self.highest()?;
 
-        // let current_priority = current().priority.load(Ordering::SeqCst);
-        // let mut chosen: Option<Arc<Thread>> = None;
-        // let mut chosen_priotiry = 0;
-
-        for i in (0..64).rev() {
-            if !(self.0)[i].is_empty() {
-                let chosen = self.0[i].pop_back().unwrap();
-                // chosen_priotiry = chosen.clone().unwrap().priority.load(Ordering::SeqCst);
+        // Keep running the current thread if it has a higher priority.
+        let current = current();
+        if current.status() == Status::Running && level < current.priority.load(SeqCst) as usize {
+            return None;
+        }
+        self.pop(level)
+    }
 
-                if chosen.priority.load(Ordering::SeqCst)
-                    < current().priority.load(Ordering::SeqCst)
-                    && current().status() == Status::Running
-                {
-                    self.0[i].push_back(chosen);
-                    return None;
-                } else {
-                    /*
-                    kprintln!(
-                        "chosen thread, id {}, name {}, at priority {}",
-                        chosen.id(),
-                        chosen.name(),
-                        chosen.priority.load(Ordering::SeqCst),
-                    );*/
-                    return Some(chosen);
-                }
+    fn reprioritize(&mut self, thread: &Thread, old: u32) {
+        let old = old as usize;
+        let idx = self.queues[old].iter().position(|t| ptr::eq(&**t, thread));
+        if let Some(thread) = idx.and_then(|idx| self.queues[old].remove(idx)) {
+            if self.queues[old].is_empty() {
+                self.ready &= !(1 << old);
             }
+            self.register(thread);
         }
-        return None;
     }
-}
 
-impl PriorityScheduler {
-    fn update_priority(&mut self) {
-        for i in 0..64 {
-            let mut temp = VecDeque::new();
-            while !self.0[i].is_empty() {
-                let mut newitem = self.0[i].pop_front().unwrap();
-                if newitem.priority.load(Ordering::SeqCst) == i as u32 {
-                    temp.push_back(newitem);
-                } else {
-                    self.0[newitem.priority.load(Ordering::SeqCst) as usize].push_back(newitem);
-                }
-            }
-            self.0[i] = temp;
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        let mut threads = Vec::new();
+        while let Some(level) = self.highest() {
+            threads.extend(self.pop(level));
         }
+        threads
     }
 }
@@ -0,0 +1,182 @@
+//! Proportional-share schedulers.
+//!
+//! Every thread holds some tickets, and gets a share of the CPU in
+//! proportion to them. [`Lottery`] draws a random ticket among the runnable
+//! threads on each decision. [`Stride`] is its deterministic counterpart:
+//! each thread advances its pass by `STRIDE1 / tickets` whenever it's
+//! picked, and the thread with the lowest pass runs next.
+//!
+//! The idle thread runs only when no other thread is runnable.
+//!
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::thread::{current, Manager, Schedule, Status, Thread};
+
+pub const TICKETS_DEFAULT: u32 = 100;
+
+/// Pass advanced by a thread with a single ticket.
+const STRIDE1: u64 = 1 << 20;
+
+fn is_idle(thread: &Arc<Thread>) -> bool {
+    Arc::ptr_eq(thread, Manager::get().idle())
+}
+
+/// The current thread, if it's still runnable and not idle.
+fn running() -> Option<Arc<Thread>> {
+    Some(current()).filter(|t| t.status() == Status::Running && !is_idle(t))
+}
+
+/// Removes and returns the idle thread, if it's the only one left and the
+/// current thread can't go on.
+fn idle(queue: &mut VecDeque<Arc<Thread>>) -> Option<Arc<Thread>> {
+    if current().status() == Status::Running {
+        return None;
+    }
+    let idx = queue.iter().position(is_idle)?;
+    queue.remove(idx)
+}
+
+/// Lottery scheduler.
+pub struct Lottery {
+    queue: VecDeque<Arc<Thread>>,
+    /// State of the xorshift generator that draws tickets.
+    seed: u64,
+}
+
+impl Default for Lottery {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+}
+
+impl Lottery {
+    fn draw(&mut self, total: u64) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed % total
+    }
+
+    /// Ready threads other than idle, with their indices in the queue.
+    fn ready<'a>(&'a self) -> impl Iterator<Item = (usize, &'a Arc<Thread>)> + 'a {
+        self.queue.iter().enumerate().filter(|(_, t)| !is_idle(t))
+    }
+}
+
+impl Schedule for Lottery {
+    fn register(&mut self, thread: Arc<Thread>) {
+        self.queue.push_back(thread)
+    }
+
+    fn schedule(&mut self) -> Option<Arc<Thread>> {
+        let tickets = |t: &Arc<Thread>| t.tickets.load(SeqCst) as u64;
+        let running = running();
+        let total = self.ready().map(|(_, t)| tickets(t)).sum::<u64>()
+            + running.as_ref().map_or(0, tickets);
+        if total == 0 {
+            return idle(&mut self.queue);
+        }
+
+        // The running thread holds the last tickets, and keeps the CPU if
+        // one of them wins.
+        let mut winner = self.draw(total);
+        let (idx, _) = self
+            .ready()
+            .find(|&(_, t)| match winner.checked_sub(tickets(t)) {
+                Some(rest) => {
+                    winner = rest;
+                    false
+                }
+                None => true,
+            })?;
+        self.queue.remove(idx)
+    }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        self.queue.drain(..).collect()
+    }
+}
+
+/// Stride scheduler.
+#[derive(Default)]
+pub struct Stride {
+    queue: VecDeque<Arc<Thread>>,
+    /// The pass of the last picked thread. Threads joining the queue start
+    /// no earlier, so they can't make up for the time they were blocked.
+    pass: u64,
+}
+
+impl Stride {
+    /// Advances the pass of `thread`, which has been picked to run.
+    fn advance(&mut self, thread: &Thread) {
+        let stride = STRIDE1 / thread.tickets.load(SeqCst).max(1) as u64;
+        self.pass = thread.pass.fetch_add(stride, SeqCst);
+    }
+}
+
+impl Schedule for Stride {
+    fn register(&mut self, thread: Arc<Thread>) {
+        thread.pass.fetch_max(self.pass, SeqCst);
+        self.queue.push_back(thread)
+    }
+
+    fn schedule(&mut self) -> Option<Arc<Thread>> {
+        // The first of the ready threads with the lowest pass.
+        let next = self
+            .queue
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !is_idle(t))
+            .min_by_key(|(_, t)| t.pass.load(SeqCst))
+            .map(|(idx, t)| (idx, t.pass.load(SeqCst)));
+
+        let running = running();
+        match (next, running) {
+            (None, None) => idle(&mut self.queue),
+            (Some((_, pass)), Some(running)) if running.pass.load(SeqCst) <= pass => {
+                self.advance(&running);
+                None
+            }
+            (None, Some(running)) => {
+                self.advance(&running);
+                None
+            }
+            (Some((idx, _)), _) => {
+                let next = self.queue.remove(idx).unwrap();
+                self.advance(&next);
+                Some(next)
+            }
+        }
+    }
+
+    fn drain(&mut self) -> Vec<Arc<Thread>> {
+        self.queue.drain(..).collect()
+    }
+}
+
+/// Sets the tickets of the current thread, at least 1.
+pub fn set_tickets(tickets: u32) {
+    current().tickets.store(tickets.max(1), SeqCst);
+}
+
+/// Returns the tickets of the current thread.
+pub fn get_tickets() -> u32 {
+    current().tickets.load(SeqCst)
+}
+
+/// Moves up to `tickets` tickets from the current thread to `to`. The
+/// current thread keeps at least 1. Returns how many were moved.
+pub fn transfer_tickets(to: &Thread, tickets: u32) -> u32 {
+    let current = current();
+    let moved = tickets.min(current.tickets.load(SeqCst) - 1);
+    current.tickets.fetch_sub(moved, SeqCst);
+    to.tickets.fetch_add(moved, SeqCst);
+    moved
+}
@@ -0,0 +1,116 @@
+//! CPU accounting
+//!
+//! Each thread counts the timer ticks it ran, how often it gave up the CPU,
+//! the time it spent blocked and ready, and the priority donations it
+//! received. [`Manager`] updates them on every switch, and
+//! [`Thread::stats`] reads them.
+//!
+//! A switch is voluntary if the thread blocked or exited, and involuntary if
+//! it was still runnable, i.e. preempted or yielding.
+//!
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
+
+use crate::sbi::timer::{clock, CLOCK_PRE_SEC};
+use crate::thread::{Manager, Thread};
+
+/// Counters of a thread, updated as it runs.
+pub(super) struct Counters {
+    ticks: AtomicU64,
+    voluntary: AtomicU64,
+    involuntary: AtomicU64,
+    /// Clock cycles spent blocked.
+    blocked: AtomicU64,
+    /// Clock cycles spent ready.
+    ready: AtomicU64,
+    donations: AtomicU64,
+    /// Clock when the thread last blocked, became ready or started running.
+    since: AtomicUsize,
+}
+
+impl Counters {
+    pub(super) fn new() -> Self {
+        Self {
+            ticks: AtomicU64::new(0),
+            voluntary: AtomicU64::new(0),
+            involuntary: AtomicU64::new(0),
+            blocked: AtomicU64::new(0),
+            ready: AtomicU64::new(0),
+            donations: AtomicU64::new(0),
+            since: AtomicUsize::new(clock()),
+        }
+    }
+
+    /// Adds the cycles since the last change to `counter`, and restarts
+    /// from `now`.
+    fn charge(&self, counter: &AtomicU64, now: usize) {
+        let since = self.since.swap(now, SeqCst);
+        counter.fetch_add(now.saturating_sub(since) as u64, SeqCst);
+    }
+
+    /// The thread was switched out, and is still runnable if `runnable`.
+    pub(super) fn switch_out(&self, now: usize, runnable: bool) {
+        match runnable {
+            true => self.involuntary.fetch_add(1, SeqCst),
+            false => self.voluntary.fetch_add(1, SeqCst),
+        };
+        self.since.store(now, SeqCst);
+    }
+
+    /// The thread was switched in, after being ready since the last change.
+    pub(super) fn switch_in(&self, now: usize) {
+        self.charge(&self.ready, now);
+    }
+
+    /// The thread was woken up, after being blocked since the last change.
+    pub(super) fn wake_up(&self, now: usize) {
+        self.charge(&self.blocked, now);
+    }
+
+    pub(super) fn donated(&self) {
+        self.donations.fetch_add(1, SeqCst);
+    }
+}
+
+/// A snapshot of the counters of a thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Timer ticks the thread was running on.
+    pub ticks: u64,
+    /// Switches on blocking or exiting.
+    pub voluntary: u64,
+    /// Switches while still runnable.
+    pub involuntary: u64,
+    /// Microseconds spent blocked.
+    pub blocked_us: u64,
+    /// Microseconds spent ready to run.
+    pub ready_us: u64,
+    /// Priority donations received.
+    pub donations: u64,
+}
+
+fn cycles_to_us(cycles: u64) -> u64 {
+    cycles * 1_000_000 / CLOCK_PRE_SEC as u64
+}
+
+impl Thread {
+    /// Returns the counters of the thread. Time in its current state is
+    /// counted once it leaves that state.
+    pub fn stats(&self) -> Stats {
+        let c = &self.counters;
+        Stats {
+            ticks: c.ticks.load(SeqCst),
+            voluntary: c.voluntary.load(SeqCst),
+            involuntary: c.involuntary.load(SeqCst),
+            blocked_us: cycles_to_us(c.blocked.load(SeqCst)),
+            ready_us: cycles_to_us(c.ready.load(SeqCst)),
+            donations: c.donations.load(SeqCst),
+        }
+    }
+}
+
+/// Charges the current thread a timer tick. Called with interrupts off.
+pub fn tick() {
+    let current = Manager::get().current.lock().clone();
+    current.counters.ticks.fetch_add(1, SeqCst);
+}
@@ -0,0 +1,166 @@
+//! Kernel timers
+//!
+//! [`add`] runs a callback once the tick count reaches a deadline, and
+//! returns a [`Handle`] to [`cancel`](Handle::cancel) it before then.
+//!
+//! Timers are kept in a hierarchical timer wheel of [`LEVELS`] levels of
+//! [`SLOTS`] slots. A level-0 slot holds the timers of one tick, and a slot
+//! of level `l` those of `SLOTS^l` ticks. A timer goes to the lowest level
+//! whose span covers its distance from now. Whenever the tick count enters
+//! the range of a higher-level slot, its timers cascade down to lower
+//! levels, so adding, cancelling and firing a timer are cheap however far
+//! its deadline is.
+//!
+//! Callbacks run in the timer interrupt with interrupts off: they may wake
+//! up threads or add timers, but must not block.
+//!
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::array;
+use core::mem;
+use core::sync::atomic::{AtomicU64, Ordering::SeqCst};
+
+use crate::sbi::timer::timer_ticks;
+use crate::sync::{Intr, Lazy, Mutex};
+
+/// Log2 of [`SLOTS`].
+const SLOT_BITS: usize = 6;
+pub const SLOTS: usize = 1 << SLOT_BITS;
+pub const LEVELS: usize = 6;
+
+/// Deadlines further than this are brought in to it, about 218 years.
+const MAX_DELTA: i64 = (1 << (SLOT_BITS * LEVELS)) - 1;
+
+struct Entry {
+    id: u64,
+    deadline: i64,
+    callback: Box<dyn FnOnce() + Send>,
+}
+
+struct Wheel {
+    /// The last tick handled.
+    now: i64,
+    slots: [[Vec<Entry>; SLOTS]; LEVELS],
+    /// Level and slot of each pending timer.
+    pending: BTreeMap<u64, (usize, usize)>,
+}
+
+static WHEEL: Lazy<Mutex<Wheel, Intr>> = Lazy::new(|| {
+    Mutex::new(Wheel {
+        now: timer_ticks(),
+        slots: array::from_fn(|_| array::from_fn(|_| Vec::new())),
+        pending: BTreeMap::new(),
+    })
+});
+
+/// The slot of `ticks` at `level`.
+fn slot(ticks: i64, level: usize) -> usize {
+    (ticks >> (SLOT_BITS * level)) as usize % SLOTS
+}
+
+impl Wheel {
+    fn insert(&mut self, entry: Entry) {
+        let delta = entry.deadline - self.now;
+        let level = (0..LEVELS)
+            .find(|level| delta >> (SLOT_BITS * (level + 1)) == 0)
+            .unwrap();
+        let slot = slot(entry.deadline, level);
+
+        self.pending.insert(entry.id, (level, slot));
+        self.slots[level][slot].push(entry);
+    }
+
+    /// Moves on to tick `now`, and returns the timers due.
+    fn advance(&mut self, now: i64) -> Vec<Entry> {
+        // Ticks before the wheel was created have nothing due.
+        if now <= self.now {
+            return Vec::new();
+        }
+        self.now = now;
+
+        // Cascade the slots whose range starts now, from the top down.
+        for level in (1..LEVELS).rev() {
+            if now % (1 << (SLOT_BITS * level)) == 0 {
+                let entries = mem::take(&mut self.slots[level][slot(now, level)]);
+                entries.into_iter().for_each(|entry| self.insert(entry));
+            }
+        }
+
+        let due = mem::take(&mut self.slots[0][slot(now, 0)]);
+        for entry in due.iter() {
+            self.pending.remove(&entry.id);
+        }
+        due
+    }
+
+    /// The earliest deadline. At each level, it's in the first non-empty
+    /// slot after the current one.
+    fn earliest(&self) -> Option<i64> {
+        (0..LEVELS)
+            .filter_map(|level| {
+                let current = slot(self.now, level);
+                (1..=SLOTS)
+                    .map(|i| &self.slots[level][(current + i) % SLOTS])
+                    .find(|entries| !entries.is_empty())
+                    .and_then(|entries| entries.iter().map(|e| e.deadline).min())
+            })
+            .min()
+    }
+}
+
+/// A pending timer.
+#[derive(Debug)]
+pub struct Handle(u64);
+
+impl Handle {
+    /// Cancels the timer. Returns `false` if it has already fired.
+    pub fn cancel(self) -> bool {
+        let mut wheel = WHEEL.lock();
+        match wheel.pending.remove(&self.0) {
+            Some((level, slot)) => {
+                wheel.slots[level][slot].retain(|entry| entry.id != self.0);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Runs `callback` on tick `deadline`, or on the next tick if it has
+/// passed.
+pub fn add<F>(deadline: i64, callback: F) -> Handle
+where
+    F: FnOnce() + Send + 'static,
+{
+    static ID: AtomicU64 = AtomicU64::new(0);
+
+    let id = ID.fetch_add(1, SeqCst);
+    let mut wheel = WHEEL.lock();
+    let deadline = deadline.clamp(wheel.now + 1, wheel.now + MAX_DELTA);
+    wheel.insert(Entry {
+        id,
+        deadline,
+        callback: Box::new(callback),
+    });
+    drop(wheel);
+
+    // The timer may be due before the timer interrupt goes off.
+    crate::sbi::timer::next();
+    Handle(id)
+}
+
+/// The earliest deadline of pending timers.
+pub fn earliest() -> Option<i64> {
+    WHEEL.lock().earliest()
+}
+
+/// Fires the timers due on the `ticks`th timer tick. Called with interrupts
+/// off, once for every tick.
+pub fn tick(ticks: i64) {
+    let due = WHEEL.lock().advance(ticks);
+    for entry in due {
+        (entry.callback)();
+    }
+}
@@ -4,9 +4,10 @@
 mod pagefault;
 mod syscall;
 
-use crate::device::{plic, virtio};
+use crate::device::{plic, uart, virtio};
 use crate::sbi;
 use crate::thread;
+use crate::userproc::signal;
 use core::arch;
 
 use riscv::register::scause::{Exception::*, Interrupt::*, Trap::*};
@@ -65,7 +66,10 @@ pub extern "C" fn trap_handler(frame: &mut Frame) {
             unsafe { riscv::register::sstatus::set_sie() };
             // Increase sepc by 1 to skip ecall.
             frame.sepc += 4;
-            frame.x[10] = syscall::syscall_handler(id, args) as usize;
+            match id {
+                syscall::SYS_SIGRETURN => signal::sigreturn(frame),
+                _ => frame.x[10] = syscall::syscall_handler(id, args) as usize,
+            }
         }
 
         Interrupt(SupervisorTimer) => {
@@ -82,6 +86,7 @@ pub extern "C" fn trap_handler(frame: &mut Frame) {
             match id as _ {
                 0 => panic!("There should be an interrupt"),
                 plic::VIRTIO0_ID => virtio::handle_interrupt(),
+                plic::UART0_ID => uart::handle_interrupt(),
                 _ => panic!("Unknown Interrupt ID: {}", id),
             }
 
@@ -110,6 +115,11 @@ pub extern "C" fn trap_handler(frame: &mut Frame) {
         }
     }
 
+    // About to return to user mode through `trap_exit_u`.
+    if frame.sstatus.spp() == SPP::User {
+        signal::deliver(frame);
+    }
+
     #[cfg(feature = "debug")]
     kprintln!("[TRAP] exit");
 }
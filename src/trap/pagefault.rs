@@ -4,7 +4,7 @@ use crate::mem::userbuf::{
 use crate::mem::PageTable;
 use crate::thread::{self};
 use crate::trap::Frame;
-use crate::userproc;
+use crate::userproc::{self, heap};
 
 use riscv::register::scause::Exception::{self, *};
 use riscv::register::sstatus::{self, SPP};
@@ -22,10 +22,18 @@ pub fn handler(frame: &mut Frame, fault: Exception, addr: usize) {
 
     unsafe { sstatus::set_sie() };
 
+    // Heap pages are allocated on first touch, also by the kernel.
+    if !present && heap::fault(addr) {
+        return;
+    }
+
+    // Faulting on a present page is an access fault: its flags deny the
+    // access, e.g. a store to a read-only page, or a user load from a guard
+    // page, which has no U bit.
     kprintln!(
         "Page fault at {:#x}: {} error {} page in {} context.",
         addr,
-        if present { "rights" } else { "not present" },
+        if present { "access" } else { "not present" },
         match fault {
             StorePageFault => "writing",
             LoadPageFault => "reading",
@@ -54,8 +62,9 @@ pub fn handler(frame: &mut Frame, fault: Exception, addr: usize) {
         }
         SPP::User => {
             kprintln!(
-                "User thread {} dying due to page fault.",
-                thread::current().name()
+                "User thread {} dying due to {} fault.",
+                thread::current().name(),
+                if present { "access" } else { "page" }
             );
             userproc::exit(-1);
         }
@@ -3,6 +3,28 @@
 
 #![allow(dead_code)]
 
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::convert::TryInto;
+use core::{mem, slice};
+
+use crate::device::console;
+use crate::fs::{self, FileSys};
+use crate::io::prelude::*;
+use crate::mem::userbuf::{read_user_buf, read_user_str, write_user_buf};
+use crate::mem::PG_SIZE;
+use crate::sbi;
+use crate::thread;
+use crate::userproc::{
+    self,
+    fd::{FdTable, FileDesc, OpenFile, OpenFlags},
+    futex, heap, mmap, signal, threads,
+};
+use crate::{OsError, Result};
+
 /* -------------------------------------------------------------------------- */
 /*                               SYSCALL NUMBER                               */
 /* -------------------------------------------------------------------------- */
@@ -19,8 +41,360 @@ const SYS_SEEK: usize = 9;
 const SYS_TELL: usize = 10;
 const SYS_CLOSE: usize = 11;
 const SYS_FSTAT: usize = 12;
+const SYS_CHDIR: usize = 15;
+const SYS_MKDIR: usize = 16;
+const SYS_PIPE: usize = 17;
+const SYS_DUP: usize = 18;
+const SYS_DUP2: usize = 19;
+const SYS_READDIR: usize = 20;
+const SYS_CONSMODE: usize = 21;
+const SYS_KILL: usize = 22;
+const SYS_SIGACTION: usize = 23;
+/// Handled by the trap handler, as it rewrites the whole frame.
+pub const SYS_SIGRETURN: usize = 24;
+const SYS_CLONE: usize = 25;
+const SYS_THREAD_JOIN: usize = 26;
+const SYS_THREAD_EXIT: usize = 27;
+const SYS_FUTEX_WAIT: usize = 28;
+const SYS_FUTEX_WAKE: usize = 29;
+const SYS_SBRK: usize = 30;
+const SYS_MMAP_ANON: usize = 31;
+const SYS_MPROTECT: usize = 32;
+
+pub fn syscall_handler(id: usize, args: [usize; 3]) -> isize {
+    let ret = match id {
+        SYS_HALT => sys_halt(),
+        SYS_EXIT => userproc::exit(args[0] as isize),
+        SYS_EXEC => sys_exec(args[0] as _, args[1] as _),
+        SYS_WAIT => sys_wait(args[0] as _),
+        SYS_REMOVE => sys_remove(args[0] as _),
+        SYS_OPEN => sys_open(args[0] as _, args[1]),
+        SYS_READ => sys_read(args[0], args[1] as _, args[2]),
+        SYS_WRITE => sys_write(args[0], args[1] as _, args[2]),
+        SYS_SEEK => sys_seek(args[0], args[1]),
+        SYS_TELL => sys_tell(args[0]),
+        SYS_CLOSE => sys_close(args[0]),
+        SYS_FSTAT => sys_fstat(args[0], args[1] as _),
+        SYS_CHDIR => sys_chdir(args[0] as _),
+        SYS_MKDIR => sys_mkdir(args[0] as _),
+        SYS_READDIR => sys_readdir(args[0] as _, args[1] as _, args[2]),
+        SYS_PIPE => sys_pipe(args[0] as _),
+        SYS_DUP => sys_dup(args[0]),
+        SYS_DUP2 => sys_dup2(args[0], args[1]),
+        SYS_CONSMODE => sys_consmode(args[0]),
+        SYS_KILL => sys_kill(args[0] as _, args[1]),
+        SYS_SIGACTION => sys_sigaction(args[0], args[1], args[2]),
+        SYS_CLONE => threads::create(args[0], args[1], args[2]),
+        SYS_THREAD_JOIN => threads::join(args[0] as _).map(|_| 0),
+        SYS_THREAD_EXIT => threads::exit(),
+        SYS_FUTEX_WAIT => futex::wait(args[0], args[1] as _).map(|_| 0),
+        SYS_FUTEX_WAKE => futex::wake(args[0], args[1]).map(|n| n as isize),
+        SYS_SBRK => heap::sbrk(args[0] as _).map(|brk| brk as isize),
+        SYS_MMAP_ANON => mmap::map(args[0], args[1], args[2]).map(|addr| addr as isize),
+        SYS_MPROTECT => mmap::protect(args[0], args[1], args[2]).map(|_| 0),
+        // TODO: LAB2 impl
+        _ => Err(OsError::UserError),
+    };
+
+    #[cfg(feature = "debug")]
+    kprintln!("[SYSCALL] {} returns {:?}", id, ret);
+
+    ret.unwrap_or(-1)
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                   PROCESS                                  */
+/* -------------------------------------------------------------------------- */
+
+fn sys_halt() -> ! {
+    fs::disk::DISKFS.unmount();
+
+    sbi::reset(
+        sbi::system_reset::Type::Shutdown,
+        sbi::system_reset::Reason::NoReason,
+    )
+}
+
+/// Arguments after which `exec` gives up. They can't fit in the stack anyway.
+const ARGV_MAX: usize = PG_SIZE / mem::size_of::<usize>();
+
+/// Reads a NULL-terminated array of strings from user memory.
+fn read_user_argv(argv: *const usize) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    if argv.is_null() {
+        return Ok(args);
+    }
+
+    for i in 0..ARGV_MAX {
+        let bytes = read_user_buf(argv.wrapping_add(i) as _, mem::size_of::<usize>())?;
+        match usize::from_ne_bytes(bytes.try_into().unwrap()) {
+            0 => return Ok(args),
+            ptr => args.push(read_user_str(ptr as _)?),
+        }
+    }
+
+    Err(OsError::ArgumentTooLong)
+}
+
+fn sys_exec(path_ptr: *const u8, argv: *const usize) -> Result<isize> {
+    let path = read_user_path(path_ptr)?;
+    let mut argv = read_user_argv(argv)?;
+    if argv.is_empty() {
+        argv.push(read_user_str(path_ptr)?);
+    }
+
+    let (file, argv) = userproc::open_executable(&path, argv)?;
+    match userproc::execute(file, argv) {
+        -1 => Err(OsError::UnknownFormat),
+        tid => Ok(tid),
+    }
+}
+
+fn sys_wait(tid: isize) -> Result<isize> {
+    userproc::wait(tid).ok_or(OsError::UserError)
+}
+
+/* -------------------------------------------------------------------------- */
+/*                                    FILE                                    */
+/* -------------------------------------------------------------------------- */
+
+/// Runs `f` on the file descriptor table of the current process.
+fn with_fdtable<T>(f: impl FnOnce(&mut FdTable) -> Result<T>) -> Result<T> {
+    let current = thread::current();
+    let userproc = current.userproc.as_ref().ok_or(OsError::UserError)?;
+    let mut fdtable = userproc.fdtable.lock();
+    f(&mut fdtable)
+}
+
+/// Reads a path from user memory, and resolves it against the working
+/// directory of the current process.
+fn read_user_path(path: *const u8) -> Result<String> {
+    let path = read_user_str(path)?;
+    let current = thread::current();
+    let userproc = current.userproc.as_ref().ok_or(OsError::UserError)?;
+    let cwd = userproc.cwd.lock();
+    Ok(fs::absolute(&cwd, &path))
+}
+
+fn sys_remove(path: *const u8) -> Result<isize> {
+    let path = read_user_path(path)?;
+    fs::remove(&path).map(|_| 0)
+}
+
+fn sys_open(path: *const u8, flags: usize) -> Result<isize> {
+    let path = read_user_path(path)?;
+    let flags = OpenFlags::from_bits(flags).ok_or(OsError::InvalidFileMode)?;
+
+    let file = match fs::open(&path) {
+        Ok(mut file) => {
+            if flags.contains(OpenFlags::TRUNC) {
+                file.set_len(0)?;
+            }
+            file
+        }
+        Err(OsError::NoSuchFile) if flags.contains(OpenFlags::CREATE) => fs::create(&path)?,
+        Err(e) => return Err(e),
+    };
+
+    let desc = FileDesc {
+        file: OpenFile::new(file, flags),
+        cloexec: true,
+    };
+    with_fdtable(|table| Ok(table.insert(desc) as isize))
+}
+
+/// Gets the open file `fd` refers to. The table is not locked afterwards,
+/// so blocking on the file doesn't block other descriptors.
+fn open_file(fd: usize) -> Result<Arc<OpenFile>> {
+    with_fdtable(|table| Ok(table.get(fd)?.file.clone()))
+}
+
+/// Reads into a user buffer page by page, so that a large request never
+/// needs a large kernel buffer.
+fn sys_read(fd: usize, buf: *mut u8, size: usize) -> Result<isize> {
+    let file = open_file(fd)?;
+    let mut bounce = vec![0u8; min(size, PG_SIZE)];
+    let mut total = 0;
+
+    while total < size {
+        let len = min(size - total, PG_SIZE);
+        let cnt = file.read(&mut bounce[..len])?;
+        write_user_buf(buf.wrapping_add(total), &bounce[..cnt])?;
+        total += cnt;
+        if cnt < len {
+            break;
+        }
+    }
+
+    Ok(total as isize)
+}
+
+/// Writes from a user buffer page by page. See [`sys_read`].
+fn sys_write(fd: usize, buf: *const u8, size: usize) -> Result<isize> {
+    let file = open_file(fd)?;
+    let mut total = 0;
+
+    while total < size {
+        let len = min(size - total, PG_SIZE);
+        let bounce = read_user_buf(buf.wrapping_add(total), len)?;
+        let cnt = file.write(&bounce)?;
+        total += cnt;
+        if cnt < len {
+            break;
+        }
+    }
+
+    Ok(total as isize)
+}
+
+fn sys_seek(fd: usize, pos: usize) -> Result<isize> {
+    open_file(fd)?.lock().seek(SeekFrom::Start(pos)).map(|_| 0)
+}
+
+fn sys_tell(fd: usize) -> Result<isize> {
+    open_file(fd)?
+        .lock()
+        .stream_position()
+        .map(|pos| pos as isize)
+}
+
+fn sys_close(fd: usize) -> Result<isize> {
+    with_fdtable(|table| table.remove(fd)).map(|_| 0)
+}
+
+fn sys_dup(fd: usize) -> Result<isize> {
+    with_fdtable(|table| table.dup(fd)).map(|fd| fd as isize)
+}
+
+fn sys_dup2(old: usize, new: usize) -> Result<isize> {
+    with_fdtable(|table| table.dup2(old, new)).map(|fd| fd as isize)
+}
+
+/// Sends `sig` to process `pid`. Init can't be signalled, lest killing it
+/// shuts the system down.
+fn sys_kill(pid: isize, sig: usize) -> Result<isize> {
+    let sig = sig.try_into()?;
+    if userproc::is_init(pid) {
+        return Err(OsError::UserError);
+    }
+    signal::kill(pid, sig).map(|_| 0)
+}
+
+/// Registers `handler` of `sig`, which returns to `restorer`. Returns the
+/// previous handler.
+fn sys_sigaction(sig: usize, handler: usize, restorer: usize) -> Result<isize> {
+    signal::set_action(sig.try_into()?, handler, restorer).map(|h| h as isize)
+}
+
+/// Sets the console input mode, and returns the previous one.
+fn sys_consmode(mode: usize) -> Result<isize> {
+    let mode = match mode {
+        0 => console::Mode::Cooked,
+        1 => console::Mode::Raw,
+        _ => return Err(OsError::UserError),
+    };
+    Ok(console::set_mode(mode) as isize)
+}
+
+/// Creates a pipe, and stores its read fd and write fd to `fds`, an `int[2]`.
+fn sys_pipe(fds: *mut u8) -> Result<isize> {
+    let (read, write) = fs::pipe::pipe();
+    let (read, write) = with_fdtable(|table| {
+        let read = table.insert(FileDesc {
+            file: OpenFile::new(read, OpenFlags::empty()),
+            cloexec: false,
+        });
+        let write = table.insert(FileDesc {
+            file: OpenFile::new(write, OpenFlags::WRONLY),
+            cloexec: false,
+        });
+        Ok((read as i32, write as i32))
+    })?;
+
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&read.to_ne_bytes());
+    bytes[4..].copy_from_slice(&write.to_ne_bytes());
+    if let Err(e) = write_user_buf(fds, &bytes) {
+        with_fdtable(|table| {
+            table.remove(read as usize)?;
+            table.remove(write as usize)
+        })?;
+        return Err(e);
+    }
+    Ok(0)
+}
+
+fn sys_chdir(path: *const u8) -> Result<isize> {
+    let path = read_user_path(path)?;
+    // Only a directory can be listed.
+    fs::read_dir(&path)?;
+
+    let current = thread::current();
+    let userproc = current.userproc.as_ref().ok_or(OsError::UserError)?;
+    *userproc.cwd.lock() = path;
+    Ok(0)
+}
+
+fn sys_mkdir(path: *const u8) -> Result<isize> {
+    let path = read_user_path(path)?;
+    fs::mkdir(&path).map(|_| 0)
+}
+
+/// Length of `dirent.name` in `user/lib/fstat.h`, including the NUL.
+const DIRENT_NAME_LEN: usize = 28;
+
+/// Layout of `dirent` in `user/lib/fstat.h`.
+#[repr(C)]
+struct UserDirent {
+    name: [u8; DIRENT_NAME_LEN],
+    kind: u32,
+}
+
+/// Stores at most `n` entries of the directory at `path` to `ents`, and
+/// returns the number of all entries. Long names are truncated.
+fn sys_readdir(path: *const u8, ents: *mut u8, n: usize) -> Result<isize> {
+    let path = read_user_path(path)?;
+    let entries = fs::read_dir(&path)?;
+
+    for (i, (name, kind)) in entries.iter().take(n).enumerate() {
+        let mut dirent = UserDirent {
+            name: [0; DIRENT_NAME_LEN],
+            kind: *kind as u32,
+        };
+        let len = min(name.len(), DIRENT_NAME_LEN - 1);
+        dirent.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                &dirent as *const _ as *const u8,
+                mem::size_of::<UserDirent>(),
+            )
+        };
+        let at = ents.wrapping_add(i * mem::size_of::<UserDirent>());
+        write_user_buf(at, bytes)?;
+    }
+
+    Ok(entries.len() as isize)
+}
+
+/// Layout of `stat` in `user/lib/fstat.h`. The padding is explicit, so
+/// that no uninitialized byte is copied to the user.
+#[repr(C)]
+struct UserStat {
+    ino: u32,
+    _pad: u32,
+    size: u64,
+}
 
-pub fn syscall_handler(_id: usize, _args: [usize; 3]) -> isize {
-    // TODO: LAB2 impl
-    -1
+fn sys_fstat(fd: usize, buf: *mut u8) -> Result<isize> {
+    let stat = open_file(fd)?.lock().stat();
+    let ustat = UserStat {
+        ino: stat.inum as u32,
+        _pad: 0,
+        size: stat.len as u64,
+    };
+    let bytes = unsafe {
+        slice::from_raw_parts(&ustat as *const _ as *const u8, mem::size_of::<UserStat>())
+    };
+    write_user_buf(buf, bytes).map(|_| 0)
 }
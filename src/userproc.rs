@@ -1,30 +1,144 @@
 //! User process.
 //!
 
+pub mod fd;
+pub mod futex;
+pub mod heap;
 mod load;
+pub mod mmap;
+pub mod signal;
+pub mod threads;
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::arch::asm;
+use core::iter;
 use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicIsize, Ordering::SeqCst};
 use riscv::register::sstatus;
 
-use crate::fs::File;
+use self::fd::FdTable;
+use self::heap::Heap;
+use self::signal::{Signal, Signals};
+use self::threads::Threads;
+use crate::fs::{self, File};
 use crate::mem::pagetable::KernelPgTable;
+use crate::sync::{Intr, Lazy, Mutex, Semaphore};
 use crate::thread;
 use crate::trap::{trap_exit_u, Frame};
+use crate::{OsError, Result};
 
 pub struct UserProc {
     #[allow(dead_code)]
     bin: File,
+    /// Files opened by this process.
+    pub fdtable: Mutex<FdTable>,
+    /// Absolute path of the working directory.
+    pub cwd: Mutex<String>,
+    /// Where the exit value goes, shared with the parent.
+    status: Arc<ExitStatus>,
+    /// Pending signals and their handlers.
+    pub signals: Mutex<Signals, Intr>,
+    /// Threads of the process.
+    pub threads: Mutex<Threads>,
+    /// Set once a thread calls [`exit`].
+    exiting: AtomicBool,
+    /// Whether the kernel started the process, i.e. it's init.
+    init: bool,
+    /// Top of the main thread's user stack. Other threads' are below.
+    stack_top: usize,
+    /// The heap, grown by `sbrk`.
+    pub heap: Mutex<Heap>,
 }
 
 impl UserProc {
-    pub fn new(file: File) -> Self {
-        Self { bin: file }
+    fn new(
+        file: File,
+        fdtable: FdTable,
+        cwd: String,
+        status: Arc<ExitStatus>,
+        brk: usize,
+        stack_top: usize,
+    ) -> Self {
+        Self {
+            bin: file,
+            fdtable: Mutex::new(fdtable),
+            cwd: Mutex::new(cwd),
+            status,
+            signals: Mutex::new(Signals::default()),
+            threads: Mutex::new(Threads::default()),
+            exiting: AtomicBool::new(false),
+            init: thread::current().userproc.is_none(),
+            stack_top,
+            heap: Mutex::new(Heap::new(brk)),
+        }
     }
 }
 
+/// Exit value of a process, kept until its parent waits for it.
+struct ExitStatus {
+    /// Tid of the thread that executed the process.
+    parent: isize,
+    value: AtomicIsize,
+    /// Up'ed once `value` is set.
+    exited: Semaphore,
+}
+
+/// Children that are not waited yet, indexed by their tids.
+static CHILDREN: Lazy<Mutex<BTreeMap<isize, Arc<ExitStatus>>, Intr>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Tid of the process that receives SIGINT on Ctrl-C, or -1 if there's none.
+///
+/// A process started by a kernel thread is in the foreground. While the
+/// foreground process waits for a child, the child is in the foreground.
+static FOREGROUND: AtomicIsize = AtomicIsize::new(-1);
+
+/// Tid of the foreground process.
+pub fn foreground() -> isize {
+    FOREGROUND.load(SeqCst)
+}
+
+/// Most interpreters a script may go through, as an interpreter may be a
+/// script itself.
+const INTERP_DEPTH_MAX: usize = 4;
+
+/// Opens the executable at `path`, which is absolute, for [`execute`].
+///
+/// A script starting with `#!interpreter [arg]` opens the interpreter
+/// instead, resolved against the root, and `argv` becomes
+/// `interpreter [arg] path argv[1..]`.
+///
+/// ## Errors
+/// - [`OsError::UnknownFormat`]: interpreters nest too deep, or a `#!` line
+///   names none.
+/// - Errors of opening the file or the interpreters.
+pub fn open_executable(path: &str, mut argv: Vec<String>) -> Result<(File, Vec<String>)> {
+    let mut path = String::from(path);
+    let mut file = fs::open(&path)?;
+
+    for _ in 0..=INTERP_DEPTH_MAX {
+        let (interp, arg) = match load::read_shebang(&mut file)? {
+            Some(shebang) => shebang,
+            None => return Ok((file, argv)),
+        };
+
+        let rest = argv.into_iter().skip(1);
+        argv = iter::once(interp.clone())
+            .chain(arg)
+            .chain([path])
+            .chain(rest)
+            .collect();
+
+        path = fs::absolute("/", &interp);
+        file = fs::open(&path)?;
+    }
+
+    Err(OsError::UnknownFormat)
+}
+
 /// Execute an object file with arguments.
 ///
 /// ## Return
@@ -44,7 +158,7 @@ pub fn execute(mut file: File, argv: Vec<String>) -> isize {
     // switch pagetables.
     let mut pt = KernelPgTable::clone();
 
-    let exec_info = match load::load_executable(&mut file, &mut pt) {
+    let exec_info = match load::load_executable(&mut file, &mut pt, &argv) {
         Ok(x) => x,
         Err(_) => unsafe {
             pt.destroy();
@@ -56,35 +170,126 @@ pub fn execute(mut file: File, argv: Vec<String>) -> isize {
     let mut frame = unsafe { MaybeUninit::<Frame>::zeroed().assume_init() };
     frame.sepc = exec_info.entry_point;
     frame.x[2] = exec_info.init_sp;
+    frame.x[10] = exec_info.argc;
+    frame.x[11] = exec_info.argv;
 
-    // Here the new process will be created.
-    let userproc = UserProc::new(file);
-
-    // TODO: (Lab2) Pass arguments to user program
+    // Here the new process will be created. It inherits open files and the
+    // working directory of the current process, if there is one.
+    let current = thread::current();
+    let (fdtable, cwd) = match &current.userproc {
+        Some(proc) => (proc.fdtable.lock().inherit(), proc.cwd.lock().clone()),
+        None => (FdTable::default(), String::from("/")),
+    };
+    let status = Arc::new(ExitStatus {
+        parent: current.id(),
+        value: AtomicIsize::new(0),
+        exited: Semaphore::new(0),
+    });
+    let userproc = Arc::new(UserProc::new(
+        file,
+        fdtable,
+        cwd,
+        status.clone(),
+        exec_info.brk,
+        exec_info.stack_top,
+    ));
 
-    thread::Builder::new(move || start(frame))
-        .pagetable(pt)
-        .userproc(userproc)
+    // Hold the lock until the main thread is recorded, in case it exits at once.
+    // The thread is named after argv[0], so listings show the program.
+    let mut threads = userproc.threads.lock();
+    let tid = thread::Builder::new(move || start(frame))
+        .name(argv.first().map_or("user", String::as_str))
+        .pagetable(Arc::new(thread::Mutex::new(pt)))
+        .userproc(userproc.clone())
         .spawn()
-        .id()
+        .thread()
+        .id();
+    threads.add(tid, 0);
+    drop(threads);
+
+    CHILDREN.lock().insert(tid, status);
+    if current.userproc.is_none() {
+        FOREGROUND.store(tid, SeqCst);
+    }
+    tid
 }
 
-/// Exits a process.
+/// Exits a process, along with all its threads.
 ///
 /// Panic if the current thread doesn't own a user process.
-pub fn exit(_value: isize) -> ! {
-    // TODO: Lab2.
+pub fn exit(value: isize) -> ! {
+    {
+        let current = thread::current();
+        let userproc = current.userproc.clone().unwrap();
+
+        // Only the first thread to exit tears the process down. The others
+        // are killed, and get here once they return to user mode.
+        if !userproc.exiting.swap(true, SeqCst) {
+            let tids: Vec<isize> = userproc
+                .threads
+                .lock()
+                .live()
+                .chain([current.id()])
+                .collect();
+
+            // Close files now rather than when the thread is freed, so that
+            // pipe readers see EOF at once.
+            userproc.fdtable.lock().clear();
+
+            // Nobody can wait for our children any more.
+            CHILDREN
+                .lock()
+                .retain(|_, child| !tids.contains(&child.parent));
+
+            userproc.status.value.store(value, SeqCst);
+            let _ = signal::kill(current.id(), Signal::Kill);
+        }
+
+        // The parent learns of the exit once no thread is left.
+        if threads::leave(&userproc) {
+            userproc.status.exited.up();
+            let _ = signal::kill(userproc.status.parent, Signal::Chld);
+        }
+    }
+
     thread::exit();
 }
 
+/// Whether `pid` is a process started by the kernel, such as init.
+pub fn is_init(pid: isize) -> bool {
+    thread::Manager::get()
+        .all()
+        .iter()
+        .any(|t| t.id() == pid && t.userproc.as_ref().map_or(false, |p| p.init))
+}
+
 /// Waits for a child thread, which must own a user process.
 ///
 /// ## Return
 /// - `Some(exit_value)`
-/// - `None`: if tid was not created by the current thread.
-pub fn wait(_tid: isize) -> Option<isize> {
-    // TODO: Lab2.
-    Some(-1)
+/// - `None`: if tid was not created by the current thread, or was waited,
+///   or if the current process is exiting.
+pub fn wait(tid: isize) -> Option<isize> {
+    let status = {
+        let mut children = CHILDREN.lock();
+        match children.get(&tid) {
+            Some(child) if child.parent == thread::current().id() => children.remove(&tid),
+            _ => None,
+        }
+    }?;
+
+    // Hand the foreground over to the child while waiting.
+    let current = thread::current().id();
+    let foreground = FOREGROUND.compare_exchange(current, tid, SeqCst, SeqCst);
+    let interrupted = status.exited.down_interruptible();
+    if foreground.is_ok() {
+        let _ = FOREGROUND.compare_exchange(tid, current, SeqCst, SeqCst);
+    }
+
+    match interrupted {
+        true => None,
+        false => Some(status.value.load(SeqCst)),
+    }
 }
 
 /// Initializes a user process in current thread.
@@ -0,0 +1,160 @@
+//! File descriptor table of a user process.
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::fs::{self, File};
+use crate::io::prelude::*;
+use crate::sync::{Mutex, MutexGuard, Primitive};
+use crate::{OsError, Result};
+
+/// Standard descriptors. A new [`FdTable`] binds them to `/dev/console`.
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+bitflags::bitflags! {
+    /// Flags of `open`. Values are the same as `O_*` in `user/lib/fcntl.h`.
+    pub struct OpenFlags: usize {
+        const WRONLY = 0x001;
+        const RDWR = 0x002;
+        const CREATE = 0x200;
+        const TRUNC = 0x400;
+    }
+}
+
+impl OpenFlags {
+    pub fn readable(&self) -> bool {
+        !self.contains(Self::WRONLY)
+    }
+
+    pub fn writable(&self) -> bool {
+        self.intersects(Self::WRONLY | Self::RDWR)
+    }
+}
+
+/// An open file description: a file, its position and the access mode it
+/// was opened with. Descriptors made by `dup` or inherited through `exec`
+/// share one, so reading through either moves both.
+pub struct OpenFile {
+    file: Mutex<File>,
+    flags: OpenFlags,
+}
+
+impl OpenFile {
+    pub fn new(file: File, flags: OpenFlags) -> Arc<Self> {
+        Arc::new(Self {
+            file: Mutex::new(file),
+            flags,
+        })
+    }
+
+    /// Locks the file for operations that don't check the access mode.
+    pub fn lock(&self) -> MutexGuard<'_, File, Primitive> {
+        self.file.lock()
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        match self.flags.readable() {
+            true => self.file.lock().read(buf),
+            false => Err(OsError::InvalidFileMode),
+        }
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<usize> {
+        match self.flags.writable() {
+            true => self.file.lock().write(buf),
+            false => Err(OsError::InvalidFileMode),
+        }
+    }
+}
+
+/// A slot in a [`FdTable`].
+#[derive(Clone)]
+pub struct FileDesc {
+    pub file: Arc<OpenFile>,
+    /// Closed rather than inherited on `exec`. Files from `open` are, as in
+    /// Pintos, while standard streams, pipes and duplicates are passed down.
+    pub cloexec: bool,
+}
+
+/// Maps file descriptors to opened files.
+pub struct FdTable(BTreeMap<usize, FileDesc>);
+
+impl Default for FdTable {
+    /// Creates a table with the standard descriptors opened on the console.
+    fn default() -> Self {
+        let console = |flags| FileDesc {
+            file: OpenFile::new(
+                fs::open("/dev/console").expect("console is always present"),
+                flags,
+            ),
+            cloexec: false,
+        };
+
+        let mut table = BTreeMap::new();
+        table.insert(STDIN, console(OpenFlags::empty()));
+        table.insert(STDOUT, console(OpenFlags::WRONLY));
+        table.insert(STDERR, console(OpenFlags::WRONLY));
+        Self(table)
+    }
+}
+
+impl FdTable {
+    /// Installs `desc` at the lowest unused descriptor.
+    pub fn insert(&mut self, desc: FileDesc) -> usize {
+        let fd = (0..).find(|fd| !self.0.contains_key(fd)).unwrap();
+        self.0.insert(fd, desc);
+        fd
+    }
+
+    pub fn get(&self, fd: usize) -> Result<&FileDesc> {
+        self.0.get(&fd).ok_or(OsError::FileNotOpened)
+    }
+
+    pub fn remove(&mut self, fd: usize) -> Result<FileDesc> {
+        self.0.remove(&fd).ok_or(OsError::FileNotOpened)
+    }
+
+    /// Duplicates `fd` to the lowest unused descriptor.
+    pub fn dup(&mut self, fd: usize) -> Result<usize> {
+        let file = self.get(fd)?.file.clone();
+        Ok(self.insert(FileDesc {
+            file,
+            cloexec: false,
+        }))
+    }
+
+    /// Duplicates `old` to `new`, closing what `new` referred to.
+    pub fn dup2(&mut self, old: usize, new: usize) -> Result<usize> {
+        let file = self.get(old)?.file.clone();
+        if old != new {
+            self.0.insert(
+                new,
+                FileDesc {
+                    file,
+                    cloexec: false,
+                },
+            );
+        }
+        Ok(new)
+    }
+
+    /// Creates the table of a child process, keeping descriptors that are
+    /// not close-on-exec.
+    pub fn inherit(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|(_, desc)| !desc.cloexec)
+                .map(|(fd, desc)| (*fd, desc.clone()))
+                .collect(),
+        )
+    }
+
+    /// Closes every descriptor.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
@@ -0,0 +1,98 @@
+//! Futexes.
+//!
+//! A futex is a 32-bit user word that threads block on, so that user
+//! mutexes and condition variables in `user/lib/thread.c` don't spin:
+//!
+//! - [`wait`] blocks only if the word still holds an expected value;
+//! - [`wake`] wakes up threads blocked on the word.
+//!
+//! Waiters are keyed by the physical address of the word, and each key has
+//! a [`Condvar`] whose lock is the table itself. The word is checked under
+//! the same lock, so a wake after the word is changed can't be missed.
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cmp::min;
+use core::convert::TryInto;
+use core::mem::size_of;
+
+use crate::mem::userbuf::read_user_buf;
+use crate::mem::{get_pte, PG_MASK};
+use crate::sync::{Condvar, Lazy, Mutex};
+use crate::{OsError, Result};
+
+/// Threads blocked on a futex.
+struct Waiters {
+    cond: Arc<Condvar>,
+    count: usize,
+}
+
+/// Waiters indexed by physical address. Keys without waiters are removed.
+static FUTEXES: Lazy<Mutex<BTreeMap<usize, Waiters>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Translates the user address of a futex.
+fn physical(addr: usize) -> Result<usize> {
+    if addr % size_of::<i32>() != 0 {
+        return Err(OsError::BadPtr);
+    }
+    match get_pte(addr) {
+        Some(entry) if entry.is_valid() && entry.is_user() => {
+            Ok(entry.pa().value() | addr & PG_MASK)
+        }
+        _ => Err(OsError::BadPtr),
+    }
+}
+
+/// Blocks on the futex at `addr` until woken up, if it holds `expected`.
+///
+/// ## Errors
+/// - [`OsError::BadPtr`]: `addr` is misaligned or not mapped.
+/// - [`OsError::UserError`]: the futex doesn't hold `expected`.
+/// - [`OsError::Interrupted`]: the process is exiting.
+pub fn wait(addr: usize, expected: i32) -> Result<()> {
+    let key = physical(addr)?;
+    let mut futexes = FUTEXES.lock();
+
+    let bytes = read_user_buf(addr as *const u8, size_of::<i32>())?;
+    if i32::from_ne_bytes(bytes[..].try_into().unwrap()) != expected {
+        return Err(OsError::UserError);
+    }
+
+    let waiters = futexes.entry(key).or_insert_with(|| Waiters {
+        cond: Arc::new(Condvar::new()),
+        count: 0,
+    });
+    waiters.count += 1;
+    let cond = waiters.cond.clone();
+    if !cond.wait_interruptible(&mut futexes) {
+        return Ok(());
+    }
+
+    // Not woken up, so still counted.
+    let waiters = futexes.get_mut(&key).unwrap();
+    waiters.count -= 1;
+    if waiters.count == 0 {
+        futexes.remove(&key);
+    }
+    Err(OsError::Interrupted)
+}
+
+/// Wakes up at most `n` threads blocked on the futex at `addr`, and returns
+/// how many are woken up.
+pub fn wake(addr: usize, n: usize) -> Result<usize> {
+    let key = physical(addr)?;
+    let mut futexes = FUTEXES.lock();
+
+    let waiters = match futexes.get_mut(&key) {
+        Some(waiters) => waiters,
+        None => return Ok(0),
+    };
+    let woken = min(n, waiters.count);
+    (0..woken).for_each(|_| waiters.cond.notify_one());
+    waiters.count -= woken;
+    if waiters.count == 0 {
+        futexes.remove(&key);
+    }
+    Ok(woken)
+}
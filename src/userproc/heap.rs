@@ -0,0 +1,108 @@
+//! Program break.
+//!
+//! The heap of a process starts right after its last ELF segment, and ends
+//! at the break, which [`sbrk`] moves. Heap pages are not allocated until
+//! they are first touched: the page fault handler asks [`fault`] to map a
+//! zero page there. Pages above a lowered break are freed at once.
+//!
+//! The break may go beyond the memory left. Touching a page then fails like
+//! touching an unmapped one, which kills the process.
+//!
+
+use core::ptr;
+
+use super::threads::STACKS_BOTTOM;
+use crate::mem::palloc::UserPool;
+use crate::mem::{PTEFlags, PageAlign, PG_MASK, PG_SIZE};
+use crate::thread;
+use crate::{OsError, Result};
+
+/// Heap of a process.
+pub struct Heap {
+    /// Where the heap starts, the initial break.
+    start: usize,
+    /// The current break.
+    brk: usize,
+}
+
+impl Heap {
+    pub(super) fn new(start: usize) -> Self {
+        Self { start, brk: start }
+    }
+
+    /// Whether `[start, end)` overlaps the heap.
+    pub(super) fn overlaps(&self, start: usize, end: usize) -> bool {
+        start < self.brk && self.start < end
+    }
+}
+
+/// Moves the break of the current process by `increment` bytes, and returns
+/// the previous break.
+///
+/// ## Errors
+/// - [`OsError::UserError`]: the break would go below the start of the heap,
+///   or into the user stacks or a mapped page.
+pub fn sbrk(increment: isize) -> Result<usize> {
+    let current = thread::current();
+    let mut heap = current.userproc.as_ref().unwrap().heap.lock();
+    let old = heap.brk;
+    let new = old
+        .checked_add_signed(increment)
+        .filter(|new| (heap.start..=STACKS_BOTTOM).contains(new))
+        .ok_or(OsError::UserError)?;
+
+    let mut pt = current.pagetable.as_ref().unwrap().lock();
+    if (old.ceil()..new)
+        .step_by(PG_SIZE)
+        .any(|va| pt.get_pte(va).map_or(false, |e| e.is_valid()))
+    {
+        return Err(OsError::UserError);
+    }
+
+    if new < old {
+        // Free pages entirely above the new break.
+        for va in (new.ceil()..old.ceil()).step_by(PG_SIZE) {
+            if let Some(pa) = pt.unmap(va) {
+                unsafe { UserPool::dealloc_pages(pa.into_va() as *mut _, 1) };
+            }
+        }
+    } else if let Some(entry) = pt.get_pte(old).filter(|e| e.is_valid()) {
+        // The page of the old break may hold data from before a shrink.
+        let len = new.min(old.ceil()) - old;
+        let at = entry.pa().into_va() + (old & PG_MASK);
+        unsafe { ptr::write_bytes(at as *mut u8, 0, len) };
+    }
+
+    heap.brk = new;
+    Ok(old)
+}
+
+/// Maps a zero page at `addr`, if it's in the heap of the current process
+/// but not mapped yet, and a page is left. Returns whether it's mapped.
+pub fn fault(addr: usize) -> bool {
+    let current = thread::current();
+    let (proc, pt) = match (&current.userproc, &current.pagetable) {
+        (Some(proc), Some(pt)) => (proc, pt),
+        _ => return false,
+    };
+
+    let heap = proc.heap.lock();
+    if !(heap.start..heap.brk).contains(&addr) {
+        return false;
+    }
+
+    let mut pt = pt.lock();
+    let page = addr.floor();
+    if pt.get_pte(page).map_or(false, |e| e.is_valid()) {
+        return false;
+    }
+
+    let buf = match unsafe { UserPool::try_alloc_pages(1) } {
+        Some(buf) => buf,
+        None => return false,
+    };
+    unsafe { ptr::write_bytes(buf, 0, PG_SIZE) };
+    let flags = PTEFlags::V | PTEFlags::R | PTEFlags::W | PTEFlags::U;
+    pt.map(buf.into(), page, PG_SIZE, flags);
+    true
+}
@@ -1,33 +1,66 @@
+use alloc::string::String;
 use alloc::vec;
-use elf_rs::{Elf, ElfFile, ProgramHeaderEntry, ProgramHeaderFlags, ProgramType};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::{mem, ptr};
+use elf_rs::{
+    Elf, ElfFile, ElfMachine, ElfType, ProgramHeaderEntry, ProgramHeaderFlags, ProgramType,
+};
 
+use super::threads::STACKS_BOTTOM;
 use crate::fs::File;
 use crate::io::prelude::*;
 use crate::mem::pagetable::{PTEFlags, PageTable};
 use crate::mem::palloc::UserPool;
-use crate::mem::{div_round_up, PageAlign, PhysAddr, PG_MASK, PG_SIZE};
+use crate::mem::{PageAlign, PhysAddr, PG_MASK, PG_SIZE};
+use crate::sbi;
 use crate::{OsError, Result};
 
+/// The main thread's user stack tops at a random page in
+/// `(USER_STACK_TOP - STACK_SPAN, USER_STACK_TOP]`.
+pub(super) const USER_STACK_TOP: usize = 0x80500000;
+pub(super) const STACK_SPAN: usize = 0x10_0000;
+
 #[derive(Debug, Clone, Copy)]
 pub(super) struct ExecInfo {
     pub entry_point: usize,
     pub init_sp: usize,
+    /// Top of the main thread's user stack, above the arguments.
+    pub stack_top: usize,
+    /// Number of arguments, passed in `a0`.
+    pub argc: usize,
+    /// User address of the argument vector, passed in `a1`.
+    pub argv: usize,
+    /// End of the last segment, where the heap starts.
+    pub brk: usize,
 }
 
 /// Loads an executable file
 ///
 /// ## Params
 /// - `pagetable`: User's pagetable. We install the mapping to executable codes into it.
+/// - `argv`: Arguments, copied onto the user stack.
 ///
 /// ## Return
-/// On success, returns `Ok(usize, usize)`:
-/// - arg0: the entry point of user program
-/// - arg1: the initial sp of user program
-pub(super) fn load_executable(file: &mut File, pagetable: &mut PageTable) -> Result<ExecInfo> {
-    let exec_info = load_elf(file, pagetable)?;
+/// On success, returns the entry point, the initial sp (below the arguments)
+/// and the arguments of the user program.
+///
+/// ## Errors
+/// - [`OsError::UnknownFormat`]: the file is not a valid executable, or user
+///   memory is exhausted.
+pub(super) fn load_executable(
+    file: &mut File,
+    pagetable: &mut PageTable,
+    argv: &[String],
+) -> Result<ExecInfo> {
+    let mut exec_info = load_elf(file, pagetable)?;
 
-    // Initialize user stack.
-    init_user_stack(pagetable, exec_info.init_sp);
+    // Initialize user stack, and pass arguments on it.
+    let stack = init_user_stack(pagetable, exec_info.stack_top).ok_or(OsError::UnknownFormat)?;
+    let sp = push_args(stack, exec_info.stack_top, argv)?;
+    exec_info.init_sp = sp;
+    exec_info.argc = argv.len();
+    exec_info.argv = sp;
 
     // Forbid modifying executable file when running
     file.deny_write();
@@ -35,7 +68,74 @@ pub(super) fn load_executable(file: &mut File, pagetable: &mut PageTable) -> Res
     Ok(exec_info)
 }
 
+/// Longest `#!` line of a script, including the newline.
+const SHEBANG_MAX: usize = 128;
+
+/// Parses the `#!interpreter [arg]` line a script starts with. Everything
+/// after the interpreter is one argument, as on Linux.
+///
+/// ## Return
+/// - `Ok(None)`: the file is not a script.
+/// - `Ok(Some((interpreter, arg)))`
+///
+/// ## Errors
+/// - [`OsError::ArgumentTooLong`]: the line is longer than [`SHEBANG_MAX`].
+/// - [`OsError::CstrFormatErr`]: the line is not valid UTF-8.
+/// - [`OsError::UnknownFormat`]: no interpreter is named.
+pub(super) fn read_shebang(file: &mut File) -> Result<Option<(String, Option<String>)>> {
+    file.rewind()?;
+
+    let mut buf = [0u8; SHEBANG_MAX];
+    let len = file.read(&mut buf)?;
+    if !buf[..len].starts_with(b"#!") {
+        return Ok(None);
+    }
+
+    let line = match buf[..len].iter().position(|b| *b == b'\n') {
+        Some(end) => &buf[2..end],
+        None if len < SHEBANG_MAX => &buf[2..len],
+        None => return Err(OsError::ArgumentTooLong),
+    };
+    let line = core::str::from_utf8(line).or(Err(OsError::CstrFormatErr))?;
+
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let (interp, arg) = match line.trim_matches(is_blank).split_once(is_blank) {
+        Some((interp, arg)) => (interp, Some(arg.trim_matches(is_blank))),
+        None => (line.trim_matches(is_blank), None),
+    };
+    if interp.is_empty() {
+        return Err(OsError::UnknownFormat);
+    }
+    Ok(Some((interp.into(), arg.map(String::from))))
+}
+
+/// Lowest address a segment may be loaded at. Page 0 stays unmapped, so
+/// that NULL dereferences fault.
+const USER_BASE: usize = PG_SIZE;
+
+/// Position-independent executables are loaded at a random page in
+/// `[PIE_BASE, PIE_BASE + PIE_SPAN)`.
+const PIE_BASE: usize = 0x1000_0000;
+const PIE_SPAN: usize = 0x1000_0000;
+
+/// Dynamic section tags locating the relocation table.
+const DT_NULL: u64 = 0;
+const DT_RELA: u64 = 7;
+const DT_RELASZ: u64 = 8;
+const DT_RELAENT: u64 = 9;
+
+/// Size of `Elf64_Rela`: offset, info and addend.
+const RELA_SIZE: usize = 3 * mem::size_of::<u64>();
+
+/// The only relocation in a statically linked PIE: the word at the offset
+/// is set to the load base plus the addend.
+const R_RISCV_RELATIVE: u64 = 3;
+
 /// Parses the specified executable file and loads segments
+///
+/// ## Errors
+/// - [`OsError::UnknownFormat`]: the file is not a RISC-V executable, or a
+///   segment doesn't fit in the file, in user memory or in the pages left.
 fn load_elf(file: &mut File, pagetable: &mut PageTable) -> Result<ExecInfo> {
     // Ensure cursor is at the beginning
     file.rewind()?;
@@ -49,25 +149,101 @@ fn load_elf(file: &mut File, pagetable: &mut PageTable) -> Result<ExecInfo> {
         Ok(Elf::Elf32(_)) | Err(_) => return Err(OsError::UnknownFormat),
     };
 
+    let header = elf.elf_header();
+    if header.machine() != ElfMachine::RISC_V {
+        return Err(OsError::UnknownFormat);
+    }
+    let base = match header.elftype() {
+        ElfType::ET_EXEC => 0,
+        ElfType::ET_DYN => pie_base(),
+        _ => return Err(OsError::UnknownFormat),
+    };
+
     // load each loadable segment into memory
-    elf.program_header_iter()
+    let mut brk = 0;
+    for phdr in elf
+        .program_header_iter()
         .filter(|p| p.ph_type() == ProgramType::LOAD)
-        .for_each(|p| load_segment(&buf, &p, pagetable));
+    {
+        brk = brk.max(load_segment(&phdr, base, pagetable)?);
+    }
+
+    if let Some(dynamic) = elf
+        .program_header_iter()
+        .find(|p| p.ph_type() == ProgramType::DYNAMIC)
+    {
+        let dynamic = dynamic.content().ok_or(OsError::UnknownFormat)?;
+        relocate(dynamic, base, pagetable)?;
+    }
 
+    // The entry point must be in an executable segment.
+    let entry_point = base.wrapping_add(header.entry_point() as usize);
+    if !pagetable
+        .get_pte(entry_point)
+        .map_or(false, |e| e.is_valid() && e.is_executable())
+    {
+        return Err(OsError::UnknownFormat);
+    }
+
+    let stack_top = stack_top();
     Ok(ExecInfo {
-        entry_point: elf.elf_header().entry_point() as _,
-        init_sp: 0x80500000,
+        entry_point,
+        init_sp: stack_top,
+        stack_top,
+        argc: 0,
+        argv: 0,
+        brk,
     })
 }
 
-/// Loads one segment and installs pagetable mappings
-fn load_segment(filebuf: &[u8], phdr: &ProgramHeaderEntry, pagetable: &mut PageTable) {
+/// A random multiple of the page size below `span`.
+fn random_pages(span: usize) -> usize {
+    // Scatter the clock, whose low bits change the fastest.
+    let seed = (sbi::timer::clock() as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    (seed >> 32) as usize % (span / PG_SIZE) * PG_SIZE
+}
+
+/// A random page to load a position-independent executable at.
+fn pie_base() -> usize {
+    PIE_BASE + random_pages(PIE_SPAN)
+}
+
+/// A random top of the main thread's user stack.
+fn stack_top() -> usize {
+    USER_STACK_TOP - random_pages(STACK_SPAN)
+}
+
+/// Loads one segment at `base` plus its address and installs pagetable
+/// mappings. Returns the end of the segment in memory.
+///
+/// Bytes of the segment beyond its file content, i.e. the BSS, are zero.
+/// Segments may share a page, e.g. the end of text and the start of rodata,
+/// which gets the permissions of both. Pages are checked against those left
+/// in the user pool, after the segments loaded before took theirs.
+fn load_segment(
+    phdr: &ProgramHeaderEntry,
+    base: usize,
+    pagetable: &mut PageTable,
+) -> Result<usize> {
     assert_eq!(phdr.ph_type(), ProgramType::LOAD);
 
-    // Meaningful contents of this segment starts from `fileoff`.
-    let fileoff = phdr.offset() as usize;
-    // But we will read and install from `read_pos`.
-    let mut readpos = fileoff & !PG_MASK;
+    let (filesz, memsz) = (phdr.filesz() as usize, phdr.memsz() as usize);
+    let content = phdr.content().ok_or(OsError::UnknownFormat)?;
+    let vaddr = base
+        .checked_add(phdr.vaddr() as usize)
+        .filter(|vaddr| *vaddr >= USER_BASE)
+        .ok_or(OsError::UnknownFormat)?;
+    let end = vaddr
+        .checked_add(memsz)
+        .filter(|end| *end <= STACKS_BOTTOM)
+        .ok_or(OsError::UnknownFormat)?;
+    if content.len() != filesz
+        || filesz > memsz
+        || vaddr & PG_MASK != phdr.offset() as usize & PG_MASK
+        || (end.ceil() - vaddr.floor()) / PG_SIZE > UserPool::free()
+    {
+        return Err(OsError::UnknownFormat);
+    }
 
     // Install flags.
     let mut leaf_flag = PTEFlags::V | PTEFlags::U | PTEFlags::R;
@@ -78,43 +254,94 @@ fn load_segment(filebuf: &[u8], phdr: &ProgramHeaderEntry, pagetable: &mut PageT
         leaf_flag |= PTEFlags::W;
     }
 
-    // Install position: `ubase`.
-    let ubase = (phdr.vaddr() as usize) & !PG_MASK;
-    let pageoff = (phdr.vaddr() as usize) & PG_MASK;
-    assert_eq!(fileoff & PG_MASK, pageoff);
+    for page in (vaddr.floor()..end).step_by(PG_SIZE) {
+        let kpage = match pagetable.get_pte(page).filter(|e| e.is_valid()).copied() {
+            Some(entry) => {
+                pagetable.set_flags(page, entry.flag() | leaf_flag);
+                entry.pa().into_va()
+            }
+            None => {
+                // The installed page will be freed when pagetable drops, which happens
+                // when user process exits. No manual resource collect is required.
+                let buf = unsafe { UserPool::try_alloc_pages(1) }.ok_or(OsError::UnknownFormat)?;
+                unsafe { ptr::write_bytes(buf, 0, PG_SIZE) };
+                pagetable.map(buf.into(), page, PG_SIZE, leaf_flag);
+                buf as usize
+            }
+        };
 
-    // How many pages need to be allocated
-    let pages = div_round_up(pageoff + phdr.memsz() as usize, PG_SIZE);
-    let mut readbytes = phdr.filesz() as usize + pageoff;
+        // Copy the file content within this page.
+        let (from, to) = (page.max(vaddr), (page + PG_SIZE).min(vaddr + filesz));
+        if from < to {
+            let src = &content[from - vaddr..to - vaddr];
+            let dst = (kpage + (from - page)) as *mut u8;
+            unsafe { ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()) };
+        }
+    }
 
-    // Allocate & map pages
-    for p in 0..pages {
-        let buf = unsafe { UserPool::alloc_pages(1) };
-        let page = unsafe { (buf as *mut [u8; PG_SIZE]).as_mut().unwrap() };
+    Ok(end)
+}
 
-        // Read `readsz` bytes, fill remaining bytes with 0.
-        let readsz = readbytes.min(PG_SIZE);
-        page[..readsz].copy_from_slice(&filebuf[readpos..readpos + readsz]);
-        page[readsz..].fill(0);
+/// Applies the relocations listed in the `dynamic` section, of an
+/// executable loaded at `base`.
+fn relocate(dynamic: &[u8], base: usize, pagetable: &PageTable) -> Result<()> {
+    let word = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
 
-        // The installed page will be freed when pagetable drops, which happens
-        // when user process exits. No manual resource collect is required.
-        let uaddr = ubase + p * PG_SIZE;
-        pagetable.map(buf.into(), uaddr, 1, leaf_flag);
+    let (mut rela, mut relasz, mut relaent) = (0, 0, RELA_SIZE as u64);
+    for entry in dynamic.chunks_exact(16) {
+        match (word(&entry[..8]), word(&entry[8..])) {
+            (DT_NULL, _) => break,
+            (DT_RELA, val) => rela = val,
+            (DT_RELASZ, val) => relasz = val,
+            (DT_RELAENT, val) => relaent = val,
+            _ => {}
+        }
+    }
+    if relaent != RELA_SIZE as u64 || relasz % relaent != 0 {
+        return Err(OsError::UnknownFormat);
+    }
 
-        readbytes -= readsz;
-        readpos += readsz;
+    for i in 0..(relasz / relaent) as usize {
+        let at = base.wrapping_add(rela as usize).wrapping_add(i * RELA_SIZE);
+        let (offset, info, addend) = unsafe {
+            (
+                *user_word(pagetable, at)?,
+                *user_word(pagetable, at.wrapping_add(8))?,
+                *user_word(pagetable, at.wrapping_add(16))?,
+            )
+        };
+        if info & 0xffff_ffff != R_RISCV_RELATIVE {
+            return Err(OsError::UnknownFormat);
+        }
+        let target = user_word(pagetable, base.wrapping_add(offset as usize))?;
+        unsafe { *target = (base as u64).wrapping_add(addend) };
     }
 
-    assert_eq!(readbytes, 0);
+    Ok(())
 }
 
-/// Initializes the user stack.
-fn init_user_stack(pagetable: &mut PageTable, init_sp: usize) {
+/// Kernel address of the aligned word at `va`, which must be loaded.
+fn user_word(pagetable: &PageTable, va: usize) -> Result<*mut u64> {
+    if va % mem::size_of::<u64>() != 0 {
+        return Err(OsError::UnknownFormat);
+    }
+    pagetable
+        .get_pte(va)
+        .filter(|e| e.is_valid() && e.is_user())
+        .map(|e| (e.pa().into_va() + (va & PG_MASK)) as *mut u64)
+        .ok_or(OsError::UnknownFormat)
+}
+
+/// Initializes the user stack, and returns the kernel address of its page,
+/// or `None` if the user pool is exhausted.
+pub(super) fn init_user_stack(
+    pagetable: &mut PageTable,
+    init_sp: usize,
+) -> Option<*mut [u8; PG_SIZE]> {
     assert!(init_sp % PG_SIZE == 0, "initial sp address misaligns");
 
     // Allocate a page from UserPool as user stack.
-    let stack_va = unsafe { UserPool::alloc_pages(1) };
+    let stack_va = unsafe { UserPool::try_alloc_pages(1) }?;
     let stack_pa = PhysAddr::from(stack_va);
 
     // Get the start address of stack page
@@ -130,4 +357,46 @@ fn init_user_stack(pagetable: &mut PageTable, init_sp: usize) {
         stack_va,
         stack_page_begin
     );
+
+    Some(stack_va as *mut [u8; PG_SIZE])
+}
+
+/// Copies `argv` to the top of the stack page, below `init_sp`:
+///
+/// ```text
+/// init_sp -> +----------------+
+///            | strings        |
+///            +----------------+
+///            | NULL           |
+///            | argv[argc - 1] |
+///            | ...            |
+///      sp -> | argv[0]        |
+///            +----------------+
+/// ```
+///
+/// Returns the new sp, which is also the address of `argv[0]`.
+fn push_args(stack: *mut [u8; PG_SIZE], init_sp: usize, argv: &[String]) -> Result<usize> {
+    let page = unsafe { stack.as_mut().unwrap() };
+    let base = init_sp - PG_SIZE;
+    let mut top = PG_SIZE;
+
+    // Strings, each ending with NUL.
+    let mut ptrs = Vec::with_capacity(argv.len() + 1);
+    for arg in argv {
+        let len = arg.len() + 1;
+        top = top.checked_sub(len).ok_or(OsError::ArgumentTooLong)?;
+        page[top..top + arg.len()].copy_from_slice(arg.as_bytes());
+        page[top + arg.len()] = 0;
+        ptrs.push(base + top);
+    }
+
+    // Pointers, with the stack aligned to 16 bytes.
+    let size = (argv.len() + 1) * mem::size_of::<usize>();
+    top = top.checked_sub(size).ok_or(OsError::ArgumentTooLong)? & !0xf;
+    for (i, ptr) in ptrs.iter().chain([&0]).enumerate() {
+        let at = top + i * mem::size_of::<usize>();
+        page[at..at + mem::size_of::<usize>()].copy_from_slice(&ptr.to_ne_bytes());
+    }
+
+    Ok(base + top)
 }
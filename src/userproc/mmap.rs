@@ -0,0 +1,130 @@
+//! Anonymous mappings and page protections.
+//!
+//! [`map`] maps zero pages at a chosen address with chosen permissions, and
+//! [`protect`] rewrites the permissions of pages already mapped, whether by
+//! [`map`], the ELF loader, or the heap. Pages are freed along with the
+//! pagetable when the process exits.
+//!
+//! A page without any permission, a guard page, stays mapped but loses
+//! [`PTEFlags::U`], since a leaf entry needs one of R, W and X. User
+//! accesses to it fault as access faults rather than not-present faults,
+//! and the user copies in [`userbuf`](crate::mem::userbuf) refuse it.
+//!
+
+use core::ptr;
+
+use super::threads::STACKS_BOTTOM;
+use crate::mem::palloc::UserPool;
+use crate::mem::{in_kernel_space, PTEFlags, PageAlign, PG_SIZE};
+use crate::thread;
+use crate::{OsError, Result};
+
+bitflags::bitflags! {
+    /// Page permissions. Values are the same as `PROT_*` in `user/lib/user.h`.
+    pub struct Prot: usize {
+        const READ = 0b001;
+        const WRITE = 0b010;
+        const EXEC = 0b100;
+    }
+}
+
+impl Prot {
+    /// Flags of a user leaf entry. Writable pages are also readable, as
+    /// W without R is reserved in Sv39.
+    fn pte_flags(self) -> PTEFlags {
+        if self.is_empty() {
+            return PTEFlags::V | PTEFlags::R;
+        }
+
+        let mut flags = PTEFlags::V | PTEFlags::U;
+        flags.set(PTEFlags::R, self.intersects(Prot::READ | Prot::WRITE));
+        flags.set(PTEFlags::W, self.contains(Prot::WRITE));
+        flags.set(PTEFlags::X, self.contains(Prot::EXEC));
+        flags
+    }
+}
+
+/// Checks that `[addr, addr + len)` is a non-empty range of user memory
+/// starting at a page, and returns its end rounded up to a page.
+fn page_range(addr: usize, len: usize) -> Result<usize> {
+    if !addr.is_aligned() || len == 0 {
+        return Err(OsError::UserError);
+    }
+    addr.checked_add(len)
+        .filter(|end| !in_kernel_space(*end))
+        .map(|end| end.ceil())
+        .ok_or(OsError::UserError)
+}
+
+/// Maps zero pages at `[addr, addr + len)` in the current process, with
+/// permissions `prot`. Returns `addr`.
+///
+/// ## Errors
+/// - [`OsError::UserError`]: `addr` is NULL or misaligned, `len` is zero,
+///   `prot` is unknown, the range overlaps a mapped page, the heap or the
+///   user stacks, or there aren't enough free pages. Nothing is mapped then.
+pub fn map(addr: usize, len: usize, prot: usize) -> Result<usize> {
+    let prot = Prot::from_bits(prot).ok_or(OsError::UserError)?;
+    let end = page_range(addr, len)?;
+    if addr == 0 || end > STACKS_BOTTOM || (end - addr) / PG_SIZE > UserPool::free() {
+        return Err(OsError::UserError);
+    }
+
+    let current = thread::current();
+    let heap = current.userproc.as_ref().unwrap().heap.lock();
+    if heap.overlaps(addr, end) {
+        return Err(OsError::UserError);
+    }
+
+    let mut pt = current.pagetable.as_ref().unwrap().lock();
+    if (addr..end)
+        .step_by(PG_SIZE)
+        .any(|va| pt.get_pte(va).map_or(false, |e| e.is_valid()))
+    {
+        return Err(OsError::UserError);
+    }
+
+    for va in (addr..end).step_by(PG_SIZE) {
+        let buf = match unsafe { UserPool::try_alloc_pages(1) } {
+            Some(buf) => buf,
+            None => {
+                // Others took the pages since the check, so undo what's mapped.
+                for va in (addr..va).step_by(PG_SIZE) {
+                    let pa = pt.unmap(va).unwrap();
+                    unsafe { UserPool::dealloc_pages(pa.into_va() as *mut _, 1) };
+                }
+                return Err(OsError::UserError);
+            }
+        };
+        unsafe { ptr::write_bytes(buf, 0, PG_SIZE) };
+        pt.map(buf.into(), va, PG_SIZE, prot.pte_flags());
+    }
+    Ok(addr)
+}
+
+/// Sets the permissions of the pages at `[addr, addr + len)` in the current
+/// process to `prot`.
+///
+/// ## Errors
+/// - [`OsError::UserError`]: `addr` is misaligned, `len` is zero, `prot` is
+///   unknown, or some page in the range is not a user mapping. Nothing is
+///   changed then.
+pub fn protect(addr: usize, len: usize, prot: usize) -> Result<()> {
+    let prot = Prot::from_bits(prot).ok_or(OsError::UserError)?;
+    let end = page_range(addr, len)?;
+
+    let current = thread::current();
+    let mut pt = current.pagetable.as_ref().unwrap().lock();
+    let mapped = (addr..end).step_by(PG_SIZE).all(|va| {
+        pt.get_pte(va)
+            .map_or(false, |e| e.is_valid() && e.is_leaf() && !e.is_global())
+    });
+    if !mapped {
+        return Err(OsError::UserError);
+    }
+
+    for va in (addr..end).step_by(PG_SIZE) {
+        pt.set_flags(va, prot.pte_flags());
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+//! Signals of user processes.
+//!
+//! [`kill`] marks a signal pending on a process. Pending signals are acted
+//! on by [`deliver`] when the process is about to return to user mode, i.e.
+//! right before `trap_exit_u`. A process blocked in the kernel sees them
+//! once its syscall returns, except that console reads are cut short.
+//!
+//! SIGKILL stays pending once raised, so that every thread of the process
+//! acts on it, and it interrupts threads blocked in joins, waits, futexes,
+//! pipes and the console.
+//!
+//! A signal is either handled by default (SIGCHLD is ignored, the others
+//! terminate the process), ignored, or caught by a user handler. A handler
+//! runs on the user stack: the interrupted registers are pushed below the
+//! user `sp`, and the handler returns to a restorer registered along with
+//! it, which calls `sigreturn` to pop them.
+//!
+
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::{mem, slice};
+
+use crate::device::console;
+use crate::mem::userbuf::{read_user_buf, write_user_buf};
+use crate::thread::{self, Manager};
+use crate::trap::Frame;
+use crate::userproc;
+use crate::{OsError, Result};
+
+use riscv::register::sstatus;
+
+/// Signals. Values are the same as `SIG*` in `user/lib/user.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Int = 2,
+    Kill = 9,
+    Term = 15,
+    Chld = 17,
+}
+
+impl TryFrom<usize> for Signal {
+    type Error = OsError;
+
+    fn try_from(sig: usize) -> Result<Self> {
+        match sig {
+            2 => Ok(Self::Int),
+            9 => Ok(Self::Kill),
+            15 => Ok(Self::Term),
+            17 => Ok(Self::Chld),
+            _ => Err(OsError::UserError),
+        }
+    }
+}
+
+impl Signal {
+    const ALL: [Self; 4] = [Self::Int, Self::Kill, Self::Term, Self::Chld];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    /// Whether the default action ignores the signal, rather than
+    /// terminating the process.
+    fn ignored_by_default(self) -> bool {
+        self == Self::Chld
+    }
+}
+
+/// Handler values that are not user addresses. Same as `SIG_DFL` and
+/// `SIG_IGN` in `user/lib/user.h`.
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+/// What a process does with a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Default,
+    Ignore,
+    Catch { handler: usize, restorer: usize },
+}
+
+impl Action {
+    fn handler(self) -> usize {
+        match self {
+            Self::Default => SIG_DFL,
+            Self::Ignore => SIG_IGN,
+            Self::Catch { handler, .. } => handler,
+        }
+    }
+}
+
+/// Signal state of a process.
+pub struct Signals {
+    /// Bit `n` is set if signal `n` is pending.
+    pending: u32,
+    /// Indexed by [`Signal::ALL`].
+    actions: [Action; Signal::ALL.len()],
+}
+
+impl Default for Signals {
+    fn default() -> Self {
+        Self {
+            pending: 0,
+            actions: [Action::Default; Signal::ALL.len()],
+        }
+    }
+}
+
+impl Signals {
+    fn action(&self, sig: Signal) -> Action {
+        self.actions[Signal::ALL.iter().position(|s| *s == sig).unwrap()]
+    }
+
+    fn set_action(&mut self, sig: Signal, action: Action) -> Action {
+        let i = Signal::ALL.iter().position(|s| *s == sig).unwrap();
+        mem::replace(&mut self.actions[i], action)
+    }
+
+    /// Marks `sig` pending, unless it would be ignored anyway.
+    fn raise(&mut self, sig: Signal) {
+        match self.action(sig) {
+            Action::Ignore => {}
+            Action::Default if sig.ignored_by_default() => {}
+            _ => self.pending |= sig.bit(),
+        }
+    }
+
+    /// Takes the next pending signal. SIGKILL goes first, and is never
+    /// taken off.
+    fn take(&mut self) -> Option<Signal> {
+        let sig = match self.pending {
+            0 => return None,
+            p if p & Signal::Kill.bit() != 0 => return Some(Signal::Kill),
+            p => Signal::try_from(p.trailing_zeros() as usize).unwrap(),
+        };
+        self.pending &= !sig.bit();
+        Some(sig)
+    }
+}
+
+/// Sends `sig` to the process whose main thread is `pid`.
+pub fn kill(pid: isize, sig: Signal) -> Result<()> {
+    let target = Manager::get()
+        .all()
+        .into_iter()
+        .find(|t| t.id() == pid && t.userproc.is_some())
+        .ok_or(OsError::NoSuchProcess)?;
+    let proc = target.userproc.as_ref().unwrap();
+    proc.signals.lock().raise(sig);
+
+    // Let a process blocked on the console notice the signal.
+    console::interrupt();
+    if sig == Signal::Kill {
+        Manager::get()
+            .all()
+            .into_iter()
+            .filter(|t| t.userproc.as_ref().map_or(false, |p| Arc::ptr_eq(p, proc)))
+            .for_each(|t| t.interrupt());
+    }
+    Ok(())
+}
+
+/// Whether the current thread has a signal to act on.
+pub fn pending() -> bool {
+    match &thread::current().userproc {
+        Some(proc) => proc.signals.lock().pending != 0,
+        None => false,
+    }
+}
+
+/// Registers a handler of `sig` for the current process, and returns the
+/// previous one. `handler` may also be `SIG_DFL` or `SIG_IGN`. SIGKILL
+/// can't be changed.
+pub fn set_action(sig: Signal, handler: usize, restorer: usize) -> Result<usize> {
+    let action = match handler {
+        _ if sig == Signal::Kill => return Err(OsError::UserError),
+        SIG_DFL => Action::Default,
+        SIG_IGN => Action::Ignore,
+        handler => Action::Catch { handler, restorer },
+    };
+
+    let current = thread::current();
+    let mut signals = current.userproc.as_ref().unwrap().signals.lock();
+    Ok(signals.set_action(sig, action).handler())
+}
+
+/// Registers saved by [`deliver`] on the user stack.
+#[repr(C)]
+struct SigFrame {
+    x: [usize; 32],
+    sepc: usize,
+}
+
+/// Acts on pending signals of the current process, which is returning to
+/// user mode with `frame`. Either terminates the process, or sets up `frame`
+/// to run a handler.
+pub fn deliver(frame: &mut Frame) {
+    loop {
+        let (sig, action) = {
+            let current = thread::current();
+            let mut signals = match &current.userproc {
+                Some(proc) => proc.signals.lock(),
+                None => return,
+            };
+            match signals.take() {
+                Some(sig) => (sig, signals.action(sig)),
+                None => return,
+            }
+        };
+
+        match action {
+            Action::Ignore => {}
+            Action::Default if sig.ignored_by_default() => {}
+            Action::Default => {
+                unsafe { sstatus::set_sie() };
+                userproc::exit(-1);
+            }
+            Action::Catch { handler, restorer } => {
+                let saved = SigFrame {
+                    x: frame.x,
+                    sepc: frame.sepc,
+                };
+                let bytes = unsafe {
+                    slice::from_raw_parts(
+                        &saved as *const _ as *const u8,
+                        mem::size_of::<SigFrame>(),
+                    )
+                };
+                // A bad user sp kills the process, even one too low to push on.
+                let sp = match frame.x[2].checked_sub(mem::size_of::<SigFrame>()) {
+                    Some(sp) if write_user_buf((sp & !0xf) as *mut u8, bytes).is_ok() => sp & !0xf,
+                    _ => {
+                        unsafe { sstatus::set_sie() };
+                        userproc::exit(-1);
+                    }
+                };
+
+                frame.x[1] = restorer;
+                frame.x[2] = sp;
+                frame.x[10] = sig as usize;
+                frame.sepc = handler;
+                return;
+            }
+        }
+    }
+}
+
+/// Restores the registers saved by [`deliver`], when a handler returns to
+/// its restorer with `sp` where it was on entry.
+pub fn sigreturn(frame: &mut Frame) {
+    let sp = frame.x[2];
+    let bytes = match read_user_buf(sp as *const u8, mem::size_of::<SigFrame>()) {
+        Ok(bytes) => bytes,
+        Err(_) => userproc::exit(-1),
+    };
+    let saved = unsafe { (bytes.as_ptr() as *const SigFrame).read_unaligned() };
+
+    // `sstatus` is never taken from user memory.
+    frame.x = saved.x;
+    frame.sepc = saved.sepc;
+}
@@ -0,0 +1,162 @@
+//! Threads of a user process.
+//!
+//! Every thread of a process is a kernel thread sharing the process's
+//! [`UserProc`] and pagetable. Each one has its own kernel stack, and a user
+//! stack page in a slot below the main thread's, whose top is random:
+//!
+//! ```text
+//! stack_top                      -> +---------------+
+//!                                   | main (slot 0) |
+//! stack_top - THREAD_STACK_SPAN  -> +---------------+
+//!                                   | slot 1        |
+//!                                   +---------------+
+//!                                   | ...           |
+//! ```
+//!
+//! Only the top page of a slot is mapped, the rest guards against overflows.
+//! Slots are mapped when first used, and reused after their thread exits.
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::mem::MaybeUninit;
+
+use super::load::{init_user_stack, STACK_SPAN, USER_STACK_TOP};
+use super::UserProc;
+use crate::mem::PG_SIZE;
+use crate::sync::Semaphore;
+use crate::thread;
+use crate::trap::Frame;
+use crate::{OsError, Result};
+
+/// Maximum number of threads in a process.
+pub const MAX_THREADS: usize = 16;
+
+/// Distance between the tops of two user stack slots.
+const THREAD_STACK_SPAN: usize = PG_SIZE * 16;
+
+/// Bottom of the lowest stack slot, wherever the main stack is. User memory
+/// below is free to use.
+pub(super) const STACKS_BOTTOM: usize =
+    USER_STACK_TOP - STACK_SPAN - MAX_THREADS * THREAD_STACK_SPAN;
+
+/// Thread bookkeeping of a process.
+#[derive(Default)]
+pub struct Threads {
+    /// Live threads, and their stack slots.
+    live: BTreeMap<isize, usize>,
+    /// Up'ed once a thread exits. Removed when the thread is joined, so
+    /// that exited threads count against [`MAX_THREADS`] until then.
+    exited: BTreeMap<isize, Arc<Semaphore>>,
+}
+
+impl Threads {
+    /// Records a new thread in `slot`.
+    pub(super) fn add(&mut self, tid: isize, slot: usize) {
+        self.live.insert(tid, slot);
+        self.exited.insert(tid, Arc::new(Semaphore::new(0)));
+    }
+
+    /// Tids of the live threads.
+    pub(super) fn live(&self) -> impl Iterator<Item = isize> + '_ {
+        self.live.keys().copied()
+    }
+
+    fn free_slot(&self) -> Option<usize> {
+        if self.exited.len() >= MAX_THREADS {
+            return None;
+        }
+        (1..MAX_THREADS).find(|slot| !self.live.values().any(|s| s == slot))
+    }
+}
+
+/// Starts a thread in the current process. It runs `entry` with `arg` in
+/// `a0`, and returns to `ret`.
+///
+/// ## Errors
+/// - [`OsError::UserError`]: there are [`MAX_THREADS`] threads already,
+///   counting exited ones not joined yet, or no page is left for the stack.
+pub fn create(entry: usize, arg: usize, ret: usize) -> Result<isize> {
+    let current = thread::current();
+    let proc = current.userproc.clone().unwrap();
+    let pagetable = current.pagetable.clone().unwrap();
+
+    // Hold the lock until the thread is recorded, in case it exits at once.
+    let mut threads = proc.threads.lock();
+    let slot = threads.free_slot().ok_or(OsError::UserError)?;
+
+    let top = proc.stack_top - slot * THREAD_STACK_SPAN;
+    {
+        let mut pt = pagetable.lock();
+        if !pt.get_pte(top - PG_SIZE).map_or(false, |e| e.is_valid()) {
+            init_user_stack(&mut pt, top).ok_or(OsError::UserError)?;
+        }
+    }
+
+    let mut frame = unsafe { MaybeUninit::<Frame>::zeroed().assume_init() };
+    frame.sepc = entry;
+    frame.x[1] = ret;
+    frame.x[2] = top;
+    frame.x[10] = arg;
+
+    let tid = thread::Builder::new(move || super::start(frame))
+        .name(current.name())
+        .pagetable(pagetable)
+        .userproc(proc.clone())
+        .spawn()
+        .thread()
+        .id();
+    threads.add(tid, slot);
+    Ok(tid)
+}
+
+/// Waits for thread `tid` of the current process to exit.
+///
+/// ## Errors
+/// - [`OsError::UserError`]: `tid` is the current thread, not a thread of
+///   the process, or was joined.
+/// - [`OsError::Interrupted`]: the process is exiting.
+pub fn join(tid: isize) -> Result<()> {
+    let current = thread::current();
+    if tid == current.id() {
+        return Err(OsError::UserError);
+    }
+
+    let exited = current
+        .userproc
+        .as_ref()
+        .unwrap()
+        .threads
+        .lock()
+        .exited
+        .remove(&tid)
+        .ok_or(OsError::UserError)?;
+    match exited.down_interruptible() {
+        true => Err(OsError::Interrupted),
+        false => Ok(()),
+    }
+}
+
+/// Exits the current thread. The process exits with 0 if it's the last one.
+pub fn exit() -> ! {
+    let last = leave(&thread::current().userproc.clone().unwrap());
+    if last {
+        super::exit(0);
+    }
+    thread::exit();
+}
+
+/// Removes the current thread from `proc`, and wakes up its joiner.
+/// Returns whether no thread is left.
+pub(super) fn leave(proc: &UserProc) -> bool {
+    let tid = thread::current().id();
+    let (exited, last) = {
+        let mut threads = proc.threads.lock();
+        threads.live.remove(&tid);
+        (threads.exited.get(&tid).cloned(), threads.live.is_empty())
+    };
+    if let Some(exited) = exited {
+        exited.up();
+    }
+    last
+}
@@ -29,7 +29,10 @@ static NAME2CASE: [(&str, fn()); 18] = [
     ("donation-three", donation::three::main),
 ];
 
-pub fn main(case: &str) {
+/// Runs the case named by the first boot argument. The rest, e.g.
+/// `sched=priority`, are for the kernel.
+pub fn main(bootargs: &str) {
+    let case = bootargs.split_whitespace().next().unwrap_or_default();
     for (name, f) in NAME2CASE.iter() {
         if case.eq(*name) {
             f();
@@ -23,7 +23,7 @@ pub fn main() {
         .spawn();
 
     assert_eq!(
-        child.status(),
+        child.thread().status(),
         Status::Ready,
         "Thread 2 should have just lowered its priority."
     );
@@ -31,7 +31,7 @@ pub fn main() {
     set_priority(PRI_DEFAULT - 2);
 
     assert_eq!(
-        child.status(),
+        child.thread().status(),
         Status::Dying,
         "Thread 2 shoud have just exited"
     );
@@ -1,7 +1,9 @@
+mod console;
 mod fs;
 mod malloc;
 mod sync;
 mod thread;
+mod timer;
 mod virtio;
 
 pub fn main() {
@@ -9,6 +11,8 @@ pub fn main() {
     sync::condvar::main();
     #[cfg(any(feature = "test-sync", feature = "test-sync-sema_fifo"))]
     sync::sema_fifo::main();
+    #[cfg(any(feature = "test-sync", feature = "test-sync-timeout"))]
+    sync::timeout::main();
 
     #[cfg(any(feature = "test-thread", feature = "test-thread-adder"))]
     thread::adder::main();
@@ -24,6 +28,28 @@ pub fn main() {
     #[cfg(any(feature = "test-thread", feature = "test-thread-spin_interrupt"))]
     thread::spin_interrupt::main();
 
+    #[cfg(feature = "test-thread-mlfqs")]
+    thread::mlfqs::main();
+    #[cfg(feature = "test-thread-policy")]
+    thread::policy::main();
+    #[cfg(feature = "test-thread-priority")]
+    thread::priority::main();
+    #[cfg(feature = "test-thread-share")]
+    thread::share::main();
+    #[cfg(feature = "test-thread-edf")]
+    thread::edf::main();
+    #[cfg(feature = "test-thread-tickless")]
+    thread::tickless::main();
+    #[cfg(feature = "test-thread-join")]
+    thread::join::main();
+    #[cfg(feature = "test-thread-stats")]
+    thread::stats::main();
+    #[cfg(feature = "test-thread-builder")]
+    thread::builder::main();
+
+    #[cfg(feature = "test-timer")]
+    timer::main();
+
     /* ------------------------------- VIRTIO TEST ------------------------------ */
 
     #[cfg(any(feature = "test-virtio", feature = "test-virtio-simple"))]
@@ -38,6 +64,15 @@ pub fn main() {
     #[cfg(feature = "test-fs-inmem")]
     fs::inmem::main();
 
+    #[cfg(feature = "test-fs-tmpfs")]
+    fs::tmpfs::main();
+
+    #[cfg(feature = "test-fs-devfs")]
+    fs::devfs::main();
+
     #[cfg(feature = "test-fs-disk")]
     fs::disk::main();
+
+    #[cfg(feature = "test-console")]
+    console::main();
 }
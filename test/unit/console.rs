@@ -0,0 +1,95 @@
+use alloc::sync::Arc;
+
+use crate::device::console::{self, Mode};
+use crate::sync::Semaphore;
+use crate::thread::{self, Status};
+
+pub fn main() {
+    cooked::test();
+    raw::test();
+    blocking::test();
+}
+
+fn feed(bytes: &[u8]) {
+    bytes.iter().for_each(|b| console::input(*b));
+}
+
+mod cooked {
+    use super::*;
+    pub(super) fn test() {
+        let mut buf = [0u8; 64];
+
+        // Reads stop at line ends.
+        feed(b"ab\rcd\n");
+        assert_eq!(console::read(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"ab\n");
+        assert_eq!(console::read(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"cd\n");
+
+        // Short reads leave the rest of the line.
+        feed(b"hello\n");
+        assert_eq!(console::read(&mut buf[..2]), Ok(2));
+        assert_eq!(console::read(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"llo\n");
+
+        // Backspace, delete and Ctrl-U.
+        feed(b"ax\x08y\x7fz\n");
+        assert_eq!(console::read(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"az\n");
+        feed(b"gone\x15\x08kept\n");
+        assert_eq!(console::read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"kept\n");
+
+        // Ctrl-D commits a partial line, or is an EOF on an empty one.
+        feed(b"part\x04\x04more\n");
+        assert_eq!(console::read(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"part");
+        assert_eq!(console::read(&mut buf), Ok(0));
+        assert_eq!(console::read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"more\n");
+    }
+}
+
+mod raw {
+    use super::*;
+    pub(super) fn test() {
+        let mut buf = [0u8; 64];
+
+        // The edited line becomes readable as is.
+        feed(b"ed");
+        assert_eq!(console::set_mode(Mode::Raw), Mode::Cooked);
+        assert_eq!(console::read(&mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"ed");
+
+        // Nothing is interpreted, and reads don't stop at line ends.
+        feed(b"a\x08\n\x04b");
+        assert_eq!(console::read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"a\x08\n\x04b");
+
+        assert_eq!(console::set_mode(Mode::Cooked), Mode::Raw);
+    }
+}
+
+mod blocking {
+    use super::*;
+    pub(super) fn test() {
+        let done = Arc::new(Semaphore::new(0));
+        let done2 = done.clone();
+        let reader = thread::spawn("reader", move || {
+            let mut buf = [0u8; 16];
+            assert_eq!(console::read(&mut buf), Ok(4));
+            assert_eq!(&buf[..4], b"abc\n");
+            done2.up();
+        });
+
+        // A partial line doesn't wake the reader.
+        feed(b"abc");
+        for _ in 0..10 {
+            thread::schedule();
+        }
+        assert_eq!(reader.thread().status(), Status::Blocked);
+
+        feed(b"\n");
+        done.down();
+    }
+}
@@ -1,2 +1,4 @@
+pub mod devfs;
 pub mod disk;
 pub mod inmem;
+pub mod tmpfs;
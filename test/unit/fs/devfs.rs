@@ -0,0 +1,88 @@
+use crate::device::virtio::{Virtio, SECTOR_SIZE};
+use crate::fs::devfs::DEVFS;
+use crate::fs::{FileSys, FileType};
+use crate::io::prelude::*;
+use crate::OsError;
+
+pub fn main() {
+    dir::test();
+    stream::test();
+    disk::test();
+}
+
+mod dir {
+    use super::*;
+    pub(super) fn test() {
+        let entries = DEVFS.read_dir("/".into()).unwrap();
+        let names: alloc::vec::Vec<_> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["console", "disk", "null", "random", "zero"]);
+        assert!(entries.iter().all(|(_, k)| *k == FileType::Device));
+
+        assert_eq!(DEVFS.open("/tty".into()).err(), Some(OsError::NoSuchFile));
+        assert_eq!(
+            DEVFS.create("/null".into()).err(),
+            Some(OsError::InvalidFileMode)
+        );
+        assert_eq!(DEVFS.remove("/null".into()), Err(OsError::InvalidFileMode));
+
+        // Every open shares the device's inode.
+        let a = DEVFS.open("/null".into()).unwrap();
+        let b = DEVFS.open("null".into()).unwrap();
+        assert_eq!(a.inum(), b.inum());
+        assert_eq!(a.stat().kind, FileType::Device);
+    }
+}
+
+mod stream {
+    use super::*;
+    pub(super) fn test() {
+        let mut buf = [0xffu8; 64];
+
+        let mut null = DEVFS.open("/null".into()).unwrap();
+        assert_eq!(null.write(&buf), Ok(64));
+        assert_eq!(null.read(&mut buf), Ok(0));
+
+        let mut zero = DEVFS.open("/zero".into()).unwrap();
+        assert_eq!(zero.read(&mut buf), Ok(64));
+        assert!(buf.iter().all(|b| *b == 0));
+
+        // Two reads of the keystream don't repeat.
+        let mut random = DEVFS.open("/random".into()).unwrap();
+        let mut other = [0u8; 64];
+        random.read_exact(&mut buf).unwrap();
+        random.read_exact(&mut other).unwrap();
+        assert_ne!(buf, other);
+        assert!(buf.iter().any(|b| *b != 0));
+    }
+}
+
+mod disk {
+    use super::*;
+    pub(super) fn test() {
+        let mut dev = DEVFS.open("/disk".into()).unwrap();
+        let capacity = Virtio::get().lock().capacity() as usize;
+        assert_eq!(dev.len(), Ok(capacity * SECTOR_SIZE));
+
+        let mut sectors = [[0u8; SECTOR_SIZE]; 2];
+        Virtio::read_sector(0, &mut sectors[0]);
+        Virtio::read_sector(1, &mut sectors[1]);
+
+        // An unaligned read spans both sectors.
+        let mut buf = [0u8; SECTOR_SIZE];
+        dev.seek(SeekFrom::Start(100)).unwrap();
+        dev.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[..SECTOR_SIZE - 100], sectors[0][100..]);
+        assert_eq!(buf[SECTOR_SIZE - 100..], sectors[1][..100]);
+
+        // Writes are refused, and leave the disk unchanged.
+        dev.seek(SeekFrom::Start(100)).unwrap();
+        assert_eq!(dev.write(&buf), Err(OsError::InvalidFileMode));
+        let mut sector = [0u8; SECTOR_SIZE];
+        Virtio::read_sector(0, &mut sector);
+        assert_eq!(sector, sectors[0]);
+
+        // Reading at the end returns nothing.
+        dev.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(dev.read(&mut buf), Ok(0));
+    }
+}
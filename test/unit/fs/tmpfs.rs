@@ -0,0 +1,93 @@
+use crate::fs::inmem::MAX_LEN;
+use crate::fs::tmpfs::TmpFs;
+use crate::fs::{FileSys, FileType};
+use crate::io::prelude::*;
+use crate::OsError;
+
+pub fn main() {
+    let fs = &TmpFs::mount(()).unwrap();
+    file::test(fs);
+    dir::test(fs);
+}
+
+mod file {
+    use super::*;
+    pub(super) fn test(fs: &TmpFs) {
+        let mut f = fs.create("/scratch".into()).unwrap();
+        assert_eq!(f.len(), Ok(0));
+
+        // Writing past the end grows the file.
+        f.write_all(&[0x1a; 600]).unwrap();
+        f.seek(SeekFrom::Start(1000)).unwrap();
+        f.write_from(0x_12_34_u16).unwrap();
+        assert_eq!(f.len(), Ok(1002));
+
+        // The gap reads as zeros, and reading at the end returns nothing.
+        let mut g = fs.open("scratch".into()).unwrap();
+        let mut buf = [0xffu8; 400];
+        g.seek(SeekFrom::Start(600)).unwrap();
+        g.read_exact(&mut buf).unwrap();
+        assert!(buf.iter().all(|b| *b == 0));
+        assert_eq!(g.read_into::<u16>(), Ok(0x_12_34));
+        assert_eq!(g.read(&mut buf), Ok(0));
+
+        // A file can't grow past its limit, nor wrap around the offset.
+        f.seek(SeekFrom::Start(MAX_LEN)).unwrap();
+        assert_eq!(f.write(&[1]), Err(OsError::FileTooLarge));
+        f.seek(SeekFrom::Start(usize::MAX)).unwrap();
+        assert_eq!(f.write(&[1]), Err(OsError::FileTooLarge));
+        assert_eq!(f.len(), Ok(1002));
+
+        // Creating again truncates, which is visible through every handle.
+        fs.create("/scratch".into()).unwrap();
+        assert_eq!(g.len(), Ok(0));
+
+        // A removed file stays usable until its last handle is dropped.
+        let inum = fs.metadata("/scratch".into()).unwrap().inum;
+        fs.remove("/scratch".into()).unwrap();
+        assert_eq!(fs.open("/scratch".into()).err(), Some(OsError::NoSuchFile));
+        f.rewind().unwrap();
+        f.write_from(7u8).unwrap();
+        assert_eq!(f.inum(), inum);
+        assert_eq!(g.len(), Ok(1));
+    }
+}
+
+mod dir {
+    use super::*;
+    pub(super) fn test(fs: &TmpFs) {
+        fs.mkdir("/a".into()).unwrap();
+        fs.mkdir("/a/b".into()).unwrap();
+        assert_eq!(fs.mkdir("/a".into()), Err(OsError::CreateExistInode));
+        assert_eq!(fs.mkdir("/x/y".into()), Err(OsError::NoSuchFile));
+
+        fs.create("/a/b/c".into())
+            .unwrap()
+            .write_all(b"tmpfs")
+            .unwrap();
+        fs.create("/a/d".into()).unwrap();
+
+        let entries = fs.read_dir("/a".into()).unwrap();
+        let names: alloc::vec::Vec<_> = entries.iter().map(|(n, k)| (n.as_str(), *k)).collect();
+        assert_eq!(names, [("b", FileType::Dir), ("d", FileType::File)]);
+
+        let stat = fs.metadata("a//b/./c".into()).unwrap();
+        assert_eq!((stat.len, stat.kind), (5, FileType::File));
+        assert_eq!(fs.metadata("/a/b".into()).unwrap().kind, FileType::Dir);
+
+        assert_eq!(fs.open("/a/b".into()).err(), Some(OsError::IsADirectory));
+        assert_eq!(fs.open("/a/d/e".into()).err(), Some(OsError::NotADirectory));
+        assert_eq!(
+            fs.create("/a/d/e".into()).err(),
+            Some(OsError::NotADirectory)
+        );
+
+        assert_eq!(fs.remove("/a/b".into()), Err(OsError::DirectoryNotEmpty));
+        fs.remove("/a/b/c".into()).unwrap();
+        fs.remove("/a/b".into()).unwrap();
+        assert_eq!(fs.read_dir("/a".into()).unwrap().len(), 1);
+
+        fs.unmount();
+        assert_eq!(fs.read_dir("/".into()).unwrap().len(), 0);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod condvar;
 pub mod sema_fifo;
+pub mod timeout;
@@ -0,0 +1,71 @@
+use alloc::sync::Arc;
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::sbi::timer::{timer_elapsed, timer_ticks};
+use crate::sync::{Condvar, Mutex, Semaphore};
+use crate::thread::{self, PRI_DEFAULT};
+
+pub fn main() {
+    // Times out, and leaves no waiter behind: a later `up` isn't lost.
+    let sema = Arc::new(Semaphore::new(0));
+    let start = timer_ticks();
+    assert!(sema.down_timeout(3));
+    assert_eq!(timer_elapsed(start), 3);
+    sema.up();
+    assert!(!sema.down_timeout(3));
+    assert_eq!(sema.value(), 0);
+
+    // Woken up in time by another thread.
+    let sema2 = sema.clone();
+    thread::spawn("upper", move || {
+        thread::sleep(1);
+        sema2.up();
+    });
+    assert!(!sema.down_timeout(5));
+
+    // A held mutex times out, a free one doesn't.
+    let mutex = Arc::new(Mutex::<i32>::new(0));
+    let (mutex2, sema2) = (mutex.clone(), sema.clone());
+    thread::spawn("holder", move || {
+        let _guard = mutex2.lock();
+        sema2.down();
+    });
+    thread::schedule();
+    assert!(mutex.try_lock_for(2).is_none());
+    sema.up();
+    assert!(mutex.try_lock_for(2).is_some());
+
+    // A timed out waiter takes its donation back.
+    let (mutex2, sema2) = (mutex.clone(), sema.clone());
+    let holder = thread::spawn("holder", move || {
+        let _guard = mutex2.lock();
+        sema2.down();
+    });
+    thread::schedule();
+    thread::set_priority(PRI_DEFAULT + 1);
+    assert!(mutex.try_lock_for(2).is_none());
+    assert_eq!(holder.thread().priority.load(SeqCst), PRI_DEFAULT);
+    thread::set_priority(PRI_DEFAULT);
+    sema.up();
+    holder.join().unwrap();
+
+    // Times out without a notification, and not with one.
+    let pair = Arc::new((Mutex::<bool>::new(false), Condvar::new()));
+    let (lock, cvar) = &*pair;
+    let mut guard = lock.lock();
+    assert!(cvar.wait_timeout(&mut guard, 2));
+
+    let pair2 = pair.clone();
+    thread::spawn("notifier", move || {
+        let (lock, cvar) = &*pair2;
+        let mut guard = lock.lock();
+        *guard = true;
+        cvar.notify_one();
+    });
+    while !*guard {
+        assert!(!cvar.wait_timeout(&mut guard, 5));
+    }
+    drop(guard);
+
+    kprintln!("timeout passed");
+}
@@ -1,5 +1,67 @@
 pub mod adder;
 pub mod block;
 pub mod bomb;
+pub mod builder;
+pub mod edf;
+pub mod join;
+pub mod mlfqs;
+pub mod policy;
+pub mod priority;
+pub mod share;
 pub mod spin_interrupt;
 pub mod spin_yield;
+pub mod stats;
+pub mod tickless;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::sbi::timer::{timer_elapsed, timer_ticks, TICKS_PER_SEC};
+use crate::sync::{Mutex, Semaphore};
+use crate::thread::scheduler::{self, Policy};
+
+/// Microseconds in a tick.
+pub const TICK_US: usize = 1_000_000 / TICKS_PER_SEC;
+
+/// Spins for `ticks` timer ticks.
+pub fn spin(ticks: i64) {
+    let start = timer_ticks();
+    while timer_elapsed(start) < ticks {}
+}
+
+/// Runs `f` under `policy`, then switches back to the default policy.
+pub fn with_policy<R>(policy: Policy, f: impl FnOnce() -> R) -> R {
+    scheduler::set_policy(policy);
+    let ret = f();
+    scheduler::set_policy(Policy::DEFAULT);
+    ret
+}
+
+/// The order in which threads ran, each recording a value.
+pub struct Order {
+    sema: Semaphore,
+    order: Mutex<Vec<u32>>,
+}
+
+impl Order {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            sema: Semaphore::new(0),
+            order: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Records `value` for the calling thread.
+    pub fn push(&self, value: u32) {
+        self.order.lock().push(value);
+        self.sema.up();
+    }
+
+    /// Waits for `n` threads to record, and returns their values in order.
+    pub fn wait(&self, n: usize) -> Vec<u32> {
+        for _ in 0..n {
+            self.sema.down();
+        }
+        self.order.lock().clone()
+    }
+}
@@ -27,7 +27,7 @@ pub fn main() {
         thread::schedule();
     }
 
-    assert_eq!(waiter.status(), Status::Blocked);
+    assert_eq!(waiter.thread().status(), Status::Blocked);
     kprintln!("Dropping mutex guard");
     drop(guard);
 
@@ -35,8 +35,8 @@ pub fn main() {
         thread::schedule();
     }
 
-    kprintln!("{:?}", waiter.status());
-    assert_eq!(waiter.status(), Status::Ready);
+    kprintln!("{:?}", waiter.thread().status());
+    assert_eq!(waiter.thread().status(), Status::Ready);
 }
 
 fn waiter_mutex(s: Arc<S>) {
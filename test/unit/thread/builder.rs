@@ -0,0 +1,64 @@
+use alloc::format;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use crate::mem::PG_SIZE;
+use crate::thread::scheduler::Class;
+use crate::thread::{self, Builder, STACK_SIZE};
+use crate::OsError;
+
+/// Uses about `depth` KiB of stack.
+fn recurse(depth: usize) -> usize {
+    let buf = [depth as u8; 1024];
+    let byte = unsafe { core::ptr::read_volatile(&buf[depth % 1024]) } as usize;
+    match depth {
+        0 => byte,
+        _ => recurse(depth - 1) + byte,
+    }
+}
+
+pub fn main() {
+    // Owned names.
+    let worker = Builder::new(|| thread::current().name().len())
+        .name(format!("worker-{}", 7))
+        .spawn();
+    assert_eq!(worker.thread().name(), "worker-7");
+    assert_eq!(worker.join(), Ok(8));
+
+    // Stacks are rounded up to pages, and may outgrow the default one.
+    let deep = Builder::new(|| recurse(2 * STACK_SIZE / 1024))
+        .name("deep")
+        .stack_size(4 * STACK_SIZE + 1)
+        .spawn();
+    let deep_thread = deep.thread().clone();
+    assert_eq!(deep_thread.stack_size(), 4 * STACK_SIZE + PG_SIZE);
+    deep.join().unwrap();
+    assert!(!deep_thread.overflow());
+    assert_eq!(thread::current().stack_size() % PG_SIZE, 0);
+
+    // Hints are recorded as given.
+    let hinted = Builder::new(|| ())
+        .name("hinted")
+        .class(Class::Batch)
+        .affinity(0b1)
+        .spawn();
+    assert_eq!(hinted.thread().class, Class::Batch);
+    assert_eq!(hinted.thread().affinity, 0b1);
+    assert_eq!(thread::current().class, Class::Normal);
+    hinted.join().unwrap();
+
+    // A detached thread runs, but can't be joined.
+    let done = Arc::new(AtomicBool::new(false));
+    let done2 = done.clone();
+    let detached = Builder::new(move || done2.store(true, SeqCst))
+        .name("detached")
+        .detached()
+        .spawn();
+    assert!(detached.thread().detached());
+    assert_eq!(detached.join(), Err(OsError::Detached));
+    while !done.load(SeqCst) {
+        thread::schedule();
+    }
+
+    kprintln!("builder passed");
+}
@@ -0,0 +1,57 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering::SeqCst};
+
+use crate::sbi::timer::TICKS_PER_SEC;
+use crate::thread::{self, Builder, Status};
+
+use super::spin;
+
+pub fn main() {
+    let stop = Arc::new(AtomicBool::new(false));
+
+    // Runs a job every 5 ticks, and always finishes it in time.
+    let jobs = Arc::new(AtomicU32::new(0));
+    let (stop2, jobs2) = (stop.clone(), jobs.clone());
+    let punctual = Builder::new(move || {
+        while !stop2.load(SeqCst) {
+            jobs2.fetch_add(1, SeqCst);
+            thread::wait_next_period();
+        }
+    })
+    .name("punctual")
+    .period(5)
+    .budget(2)
+    .spawn();
+
+    // Never finishes a job, and is throttled to 1 tick per period.
+    let stop2 = stop.clone();
+    let late = Builder::new(move || while !stop2.load(SeqCst) {})
+        .name("late")
+        .period(5)
+        .budget(1)
+        .deadline(4)
+        .spawn();
+
+    // Real-time threads preempt this spinning thread.
+    spin(2 * TICKS_PER_SEC as i64);
+    let jobs = jobs.load(SeqCst);
+    let punctual_misses = punctual.thread().realtime.as_ref().unwrap().misses();
+    let late_misses = late.thread().realtime.as_ref().unwrap().misses();
+    kprintln!(
+        "{} jobs, misses: punctual {}, late {}",
+        jobs,
+        punctual_misses,
+        late_misses
+    );
+    assert!((3..=5).contains(&jobs));
+    assert_eq!(punctual_misses, 0);
+    assert!(late_misses >= 3);
+
+    stop.store(true, SeqCst);
+    for handle in [punctual, late].iter() {
+        while handle.thread().status() != Status::Dying {
+            thread::schedule();
+        }
+    }
+    kprintln!("edf passed");
+}
@@ -0,0 +1,31 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use crate::thread::{self, Status};
+use crate::OsError;
+
+pub fn main() {
+    // Joining returns what the thread returned.
+    let adder = thread::spawn("adder", || (1..=10).sum::<u32>());
+    assert_eq!(adder.join(), Ok(55));
+
+    let greeter = thread::spawn("greeter", || String::from("hello"));
+    thread::schedule();
+    assert_eq!(greeter.thread().status(), Status::Dying);
+    assert_eq!(greeter.join().as_deref(), Ok("hello"));
+
+    // A panic fails the joiner only.
+    let bomb = thread::spawn("bomb", || -> u32 { panic!("expected panic") });
+    assert_eq!(bomb.join(), Err(OsError::Panicked));
+
+    // Without a handle, the thread still runs to its end.
+    let done = Arc::new(AtomicBool::new(false));
+    let done2 = done.clone();
+    drop(thread::spawn("detached", move || done2.store(true, SeqCst)));
+    while !done.load(SeqCst) {
+        thread::schedule();
+    }
+
+    kprintln!("join passed");
+}
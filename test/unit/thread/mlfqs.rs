@@ -0,0 +1,56 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use crate::sbi::timer::TICKS_PER_SEC;
+use crate::thread::scheduler::mlfqs::{Fixed, NICE_MAX, NICE_MIN};
+use crate::thread::scheduler::Policy;
+use crate::thread::{self, PRI_MAX};
+
+use super::{spin, with_policy};
+
+pub fn main() {
+    with_policy(Policy::Mlfqs, || {
+        let third = Fixed::from_int(1) / Fixed::from_int(3);
+        assert_eq!((third * 100).round(), 33);
+        assert_eq!((third * Fixed::from_int(-3)).round(), -1);
+        assert_eq!(Fixed::from_int(7).trunc(), 7);
+
+        assert_eq!(thread::get_nice(), 0);
+        thread::set_nice(NICE_MAX + 5);
+        assert_eq!(thread::get_nice(), NICE_MAX);
+        thread::set_nice(NICE_MIN - 5);
+        assert_eq!(thread::get_nice(), NICE_MIN);
+        thread::set_nice(0);
+
+        // Priorities come from the scheduler.
+        let priority = thread::get_priority();
+        thread::set_priority(PRI_MAX);
+        assert_eq!(thread::get_priority(), priority);
+
+        // A spinning thread keeps the load up, and takes CPU time.
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop2 = stop.clone();
+        let spinner = thread::spawn("spinner", move || while !stop2.load(SeqCst) {});
+
+        spin(2 * TICKS_PER_SEC as i64);
+        kprintln!(
+            "load_avg {}, recent_cpu {}",
+            thread::get_load_avg(),
+            thread::get_recent_cpu()
+        );
+        assert!(thread::get_load_avg() > 0);
+        assert!(thread::get_recent_cpu() > 0);
+
+        // Being nice lowers the priority at once, so the spinner runs first.
+        let priority = thread::get_priority();
+        thread::set_nice(5);
+        assert!(thread::get_priority() < priority);
+        thread::set_nice(0);
+
+        stop.store(true, SeqCst);
+        while spinner.thread().status() != thread::Status::Dying {
+            thread::schedule();
+        }
+    });
+    kprintln!("mlfqs passed");
+}
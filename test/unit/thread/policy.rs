@@ -0,0 +1,30 @@
+use crate::sbi::interrupt;
+use crate::thread::scheduler::{self, Policy};
+use crate::thread::Builder;
+
+use super::{with_policy, Order};
+
+/// Spawns two threads of lower priorities under FCFS, switches to the
+/// priority scheduler, then blocks: the migrated threads run by priority.
+pub fn main() {
+    assert!("fifo".parse::<Policy>().is_err());
+    let order = Order::new();
+
+    with_policy(Policy::Priority, || {
+        let old = interrupt::set(false);
+        scheduler::set_policy(Policy::Fcfs);
+        for priority in [10, 20] {
+            let order = order.clone();
+            Builder::new(move || order.push(priority))
+                .priority(priority)
+                .name("policy")
+                .spawn();
+        }
+        scheduler::set_policy(Policy::Priority);
+        assert_eq!(scheduler::policy(), Policy::Priority);
+        interrupt::set(old);
+
+        assert_eq!(order.wait(2), [20, 10]);
+    });
+    kprintln!("policy passed");
+}
@@ -0,0 +1,38 @@
+use alloc::vec::Vec;
+
+use crate::sbi::interrupt;
+use crate::thread::scheduler::Policy;
+use crate::thread::{self, Builder, PRI_MAX};
+
+use super::{with_policy, Order};
+
+/// Raises the priority of a ready thread past another one: it must move to
+/// its new level and run first. Priorities beyond the levels are clamped.
+pub fn main() {
+    let order = Order::new();
+
+    with_policy(Policy::Priority, || {
+        let old = interrupt::set(false);
+        let threads: Vec<_> = [10, 20]
+            .iter()
+            .map(|&priority| {
+                let order = order.clone();
+                Builder::new(move || order.push(thread::get_priority()))
+                    .priority(priority)
+                    .name("priority")
+                    .spawn()
+            })
+            .collect();
+        threads[0].thread().set_priority(25);
+        interrupt::set(old);
+
+        assert_eq!(order.wait(2), [25, 20]);
+
+        // Priorities past the highest level are taken as the highest.
+        let priority = thread::get_priority();
+        thread::set_priority(PRI_MAX + 1);
+        assert_eq!(thread::get_priority(), PRI_MAX);
+        thread::set_priority(priority);
+    });
+    kprintln!("priority passed");
+}
@@ -0,0 +1,64 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering::SeqCst};
+
+use crate::sbi::timer::TICKS_PER_SEC;
+use crate::thread::scheduler::{self, Policy};
+use crate::thread::{self, Status};
+
+use super::with_policy;
+
+/// Runs two spinners holding 300 and 100 tickets for `secs` seconds, and
+/// returns how many loops each made.
+fn race(policy: Policy, secs: usize) -> (u64, u64) {
+    with_policy(policy, || {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let spinners = [300, 100].map(|tickets| {
+            let stop = stop.clone();
+            let loops = Arc::new(AtomicU64::new(0));
+            let loops2 = loops.clone();
+            let spinner = thread::spawn("spinner", move || {
+                thread::set_tickets(tickets);
+                while !stop.load(SeqCst) {
+                    loops2.fetch_add(1, SeqCst);
+                }
+            });
+            (spinner, loops)
+        });
+
+        thread::sleep((secs * TICKS_PER_SEC) as i64);
+        stop.store(true, SeqCst);
+        for (spinner, _) in spinners.iter() {
+            while spinner.thread().status() != Status::Dying {
+                thread::schedule();
+            }
+        }
+
+        let [(_, rich), (_, poor)] = spinners;
+        let (rich, poor) = (rich.load(SeqCst), poor.load(SeqCst));
+        kprintln!("{:?}: {} vs {} loops", policy, rich, poor);
+        (rich, poor)
+    })
+}
+
+pub fn main() {
+    assert_eq!(thread::get_tickets(), scheduler::share::TICKETS_DEFAULT);
+    let receiver = thread::spawn("receiver", || {});
+    assert_eq!(thread::transfer_tickets(receiver.thread(), 30), 30);
+    assert_eq!(receiver.thread().tickets.load(SeqCst), 130);
+    assert_eq!(thread::transfer_tickets(receiver.thread(), 1000), 69);
+    assert_eq!(thread::get_tickets(), 1);
+    thread::set_tickets(0);
+    assert_eq!(thread::get_tickets(), 1);
+    thread::set_tickets(scheduler::share::TICKETS_DEFAULT);
+
+    // Stride is exact up to a tick; lottery only in expectation. Over 300
+    // ticks, the poor spinner wins 1/4 of them give or take 0.025, so a
+    // ratio out of 1.5..6 is more than four deviations off.
+    let (rich, poor) = race(Policy::Stride, 3);
+    assert!(rich > poor * 2 && rich < poor * 4);
+    let (rich, poor) = race(Policy::Lottery, 30);
+    assert!(rich * 2 > poor * 3 && rich < poor * 6);
+
+    kprintln!("share passed");
+}
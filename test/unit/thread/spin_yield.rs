@@ -12,7 +12,8 @@ pub fn main() {
 }
 
 fn spin() {
-    let name = thread::current().name();
+    let current = thread::current();
+    let name = current.name();
     for iter in 0..15 {
         kprintln!("Yield {} from thread {}", iter, name);
         thread::schedule();
@@ -0,0 +1,58 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use crate::sync::{Mutex, Sleep};
+use crate::thread::scheduler::Policy;
+use crate::thread::{self, Builder, Status, PRI_DEFAULT};
+
+use super::{spin, with_policy, TICK_US};
+
+pub fn main() {
+    // Running charges ticks.
+    let before = thread::current().stats();
+    spin(3);
+    assert!(thread::current().stats().ticks >= before.ticks + 2);
+
+    // Sleeping is a voluntary switch, and time blocked.
+    let sleeper = thread::spawn("sleeper", || {
+        thread::sleep(2);
+        thread::current().stats()
+    });
+    let stats = sleeper.join().unwrap();
+    kprintln!("sleeper: {:?}", stats);
+    assert!(stats.voluntary >= 1);
+    assert!(stats.blocked_us >= TICK_US as u64);
+
+    // A spinner is preempted by the timer, and waits ready for its turn.
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop2 = stop.clone();
+    let spinner = thread::spawn("spinner", move || {
+        while !stop2.load(SeqCst) {}
+        thread::current().stats()
+    });
+    spin(5);
+    stop.store(true, SeqCst);
+    let stats = spinner.join().unwrap();
+    kprintln!("spinner: {:?}", stats);
+    assert!(stats.involuntary >= 1);
+    assert!(stats.ready_us > 0);
+    assert!(stats.ticks >= 1);
+
+    // Waiting on a lock donates to its holder.
+    with_policy(Policy::Priority, || {
+        let lock = Arc::new(Mutex::<(), Sleep>::new(()));
+        let lock2 = lock.clone();
+        let before = thread::current().stats().donations;
+        let guard = lock.lock();
+        let waiter = Builder::new(move || drop(lock2.lock()))
+            .name("waiter")
+            .priority(PRI_DEFAULT + 10)
+            .spawn();
+        assert_eq!(waiter.thread().status(), Status::Blocked);
+        assert_eq!(thread::current().stats().donations, before + 1);
+        drop(guard);
+        waiter.join().unwrap();
+    });
+
+    kprintln!("stats passed");
+}
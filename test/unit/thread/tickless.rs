@@ -0,0 +1,23 @@
+use crate::sbi::timer::{time_us, timer_elapsed, timer_ticks, CLOCK_PRE_SEC};
+use crate::thread;
+
+use super::TICK_US;
+
+pub fn main() {
+    assert_eq!(CLOCK_PRE_SEC % 1_000_000, 0);
+
+    // Shorter than a tick, so only a one-shot timer wakes it up in time.
+    for us in [300, 2_500, 40_000] {
+        let start = time_us();
+        thread::sleep_us(us);
+        let slept = time_us() - start;
+        kprintln!("sleep_us({}) took {}us", us, slept);
+        assert!(slept >= us && slept < us + TICK_US / 4);
+    }
+
+    // Ticks keep counting while the CPU idles.
+    let start = timer_ticks();
+    thread::sleep(3);
+    assert_eq!(timer_elapsed(start), 3);
+    kprintln!("tickless passed");
+}
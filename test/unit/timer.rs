@@ -0,0 +1,34 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::sbi::timer::timer_ticks;
+use crate::sync::{Intr, Mutex};
+use crate::thread;
+use crate::timer::{self, SLOTS};
+
+pub fn main() {
+    let fired = Arc::new(Mutex::<Vec<(i64, i64)>, Intr>::new(Vec::new()));
+    let start = timer_ticks();
+
+    // Records the delta of each timer fired, and when it fired.
+    let add = |delta: i64| {
+        let fired = fired.clone();
+        timer::add(start + delta, move || {
+            fired.lock().push((delta, timer_ticks() - start))
+        })
+    };
+    let (three, one, two) = (add(3), add(1), add(2));
+    // Cascades down from level 1.
+    let far = add(SLOTS as i64 + 6);
+
+    assert!(two.cancel());
+    thread::sleep(4);
+    assert!(!three.cancel());
+    assert!(!one.cancel());
+    assert_eq!(*fired.lock(), [(1, 1), (3, 3)]);
+
+    thread::sleep(SLOTS as i64 + 3);
+    assert!(!far.cancel());
+    assert_eq!(fired.lock()[2], (SLOTS as i64 + 6, SLOTS as i64 + 6));
+    kprintln!("timer passed");
+}
@@ -0,0 +1,21 @@
+/** The first user program, booted by the kernel with feature `shell`.
+
+   Runs the shell, and runs it again if the kernel kills it. The
+   system shuts down once init exits. */
+
+#include "user.h"
+
+#define KILLED_EXIT -1
+
+void main() {
+    const char* argv[] = {"sh", NULL};
+
+    for (;;) {
+        int pid = exec(argv[0], argv);
+        assert(pid > 0, "cannot run \"%s\"", argv[0]);
+
+        int status = wait(pid);
+        if (status != KILLED_EXIT) break;
+        printf("init: %s was killed, restarting\n", argv[0]);
+    }
+}
@@ -0,0 +1,302 @@
+/** A small shell.
+
+   Runs programs from the disk with arguments, and supports pipelines
+   (`a | b`), redirections (`< in`, `> out`) and background jobs (a
+   trailing `&`). Builtins are cd, ls, cat, wait and exit. A `#` starts
+   a comment.
+
+   Usage: sh [script]
+
+   Reads commands from SCRIPT instead of stdin, without prompting. A
+   script starting with `#!/sh` can be run by itself. */
+
+#include "user.h"
+
+#define LINE_MAX 256
+#define TOKS_MAX 64
+#define ARGS_MAX 16
+#define STAGES_MAX 8
+#define JOBS_MAX 16
+#define DIRENTS_MAX 128
+
+typedef struct {
+    const char* argv[ARGS_MAX + 1];
+    const char* in;  /* File to redirect stdin from, or NULL. */
+    const char* out; /* File to redirect stdout to, or NULL. */
+} command;
+
+/* Background jobs that are not waited yet. */
+static int jobs[JOBS_MAX];
+static int njobs;
+
+/* ------------------------------------------------------------------------ */
+/*                                 Builtins                                 */
+/* ------------------------------------------------------------------------ */
+
+static int cd(int argc, const char* argv[]) {
+    const char* dir = argc > 1 ? argv[1] : "/";
+    if (chdir(dir) != 0) {
+        fprintf(2, "cd: %s: not a directory\n", dir);
+        return 1;
+    }
+    return 0;
+}
+
+static int ls_one(const char* dir) {
+    static dirent ents[DIRENTS_MAX];
+    int n = readdir(dir, ents, DIRENTS_MAX);
+    if (n < 0) {
+        fprintf(2, "ls: %s: not a directory\n", dir);
+        return 1;
+    }
+
+    for (int i = 0; i < n && i < DIRENTS_MAX; i++) {
+        if (ents[i].name[0] == '.') continue;
+        printf("%s%s\n", ents[i].name, ents[i].type == T_DIR ? "/" : "");
+    }
+    return 0;
+}
+
+static int ls(int argc, const char* argv[]) {
+    int ret = 0;
+    if (argc == 1) return ls_one(".");
+    for (int i = 1; i < argc; i++) {
+        if (argc > 2) printf("%s:\n", argv[i]);
+        ret |= ls_one(argv[i]);
+    }
+    return ret;
+}
+
+static void cat_fd(int fd) {
+    char buf[512];
+    int n;
+    while ((n = read(fd, buf, sizeof buf)) > 0) write(1, buf, n);
+}
+
+static int cat(int argc, const char* argv[]) {
+    int ret = 0;
+    if (argc == 1) cat_fd(0);
+    for (int i = 1; i < argc; i++) {
+        int fd = open(argv[i], O_RDONLY);
+        if (fd < 0) {
+            fprintf(2, "cat: %s: cannot open\n", argv[i]);
+            ret = 1;
+            continue;
+        }
+        cat_fd(fd);
+        close(fd);
+    }
+    return ret;
+}
+
+static int wait_jobs(int argc, const char* argv[]) {
+    for (int i = 0; i < njobs; i++) printf("[%d] exited with %d\n", jobs[i], wait(jobs[i]));
+    njobs = 0;
+    return 0;
+}
+
+static int exit_(int argc, const char* argv[]) {
+    exit(argc > 1 ? atoi(argv[1]) : NORMAL_EXIT);
+    return 0;
+}
+
+static const struct {
+    const char* name;
+    int (*run)(int argc, const char* argv[]);
+} builtins[] = {
+    {"cd", cd}, {"ls", ls}, {"cat", cat}, {"wait", wait_jobs}, {"exit", exit_},
+};
+
+/* Finds the builtin named NAME, or returns -1. */
+static int builtin(const char* name) {
+    for (int i = 0; i < sizeof builtins / sizeof builtins[0]; i++)
+        if (strcmp(name, builtins[i].name) == 0) return i;
+    return -1;
+}
+
+/* ------------------------------------------------------------------------ */
+/*                                  Parsing                                 */
+/* ------------------------------------------------------------------------ */
+
+static int is_space(char c) { return c == ' ' || c == '\t'; }
+static int is_special(char c) { return c == '|' || c == '<' || c == '>' || c == '&'; }
+
+/* Reads a line from stdin into BUF, without the line break. Returns -1
+   at EOF. */
+static int read_line(char* buf, int size) {
+    int n = 0;
+    char c;
+
+    while (n < size - 1) {
+        if (read(0, &c, 1) <= 0) {
+            if (n == 0) return -1;
+            break;
+        }
+        if (c == '\n' || c == '\r') break;
+        buf[n++] = c;
+    }
+    buf[n] = '\0';
+    return n;
+}
+
+/* Splits LINE into words and special characters, which are copied to
+   BUF, each ending with NUL. Returns the number of tokens, or -1 if
+   there are too many. */
+static int tokenize(const char* line, char* buf, char* toks[], int max) {
+    int n = 0;
+
+    for (;;) {
+        while (is_space(*line)) line++;
+        if (*line == '\0' || *line == '#') return n;
+        if (n == max) return -1;
+
+        toks[n++] = buf;
+        if (is_special(*line)) {
+            *buf++ = *line++;
+        } else {
+            while (*line && !is_space(*line) && !is_special(*line)) *buf++ = *line++;
+        }
+        *buf++ = '\0';
+    }
+}
+
+/* Parses TOKS into pipeline stages. Returns the number of stages, or -1
+   on a syntax error. */
+static int parse(char* toks[], int ntoks, command cmds[], int* background) {
+    int n = 0, argc = 0;
+
+    *background = 0;
+    memset(&cmds[0], 0, sizeof(command));
+
+    for (int i = 0; i < ntoks; i++) {
+        char* tok = toks[i];
+        command* cmd = &cmds[n];
+
+        if (strcmp(tok, "|") == 0) {
+            if (argc == 0 || n + 1 == STAGES_MAX) goto error;
+            n++, argc = 0;
+            memset(&cmds[n], 0, sizeof(command));
+        } else if (strcmp(tok, "<") == 0 || strcmp(tok, ">") == 0) {
+            if (i + 1 == ntoks || is_special(toks[i + 1][0])) goto error;
+            if (tok[0] == '<') cmd->in = toks[++i];
+            else cmd->out = toks[++i];
+        } else if (strcmp(tok, "&") == 0) {
+            if (i + 1 != ntoks) goto error;
+            *background = 1;
+        } else {
+            if (argc == ARGS_MAX) goto error;
+            cmd->argv[argc++] = tok;
+        }
+    }
+
+    if (argc == 0) {
+        if (n == 0 && ntoks == 0) return 0;
+        goto error;
+    }
+    return n + 1;
+
+error:
+    fprintf(2, "sh: syntax error\n");
+    return -1;
+}
+
+/* ------------------------------------------------------------------------ */
+/*                                 Execution                                */
+/* ------------------------------------------------------------------------ */
+
+static int argc_of(command* cmd) {
+    int argc = 0;
+    while (cmd->argv[argc]) argc++;
+    return argc;
+}
+
+/* Points FD to the file at PATH. */
+static int redirect(int fd, const char* path, int flags) {
+    int file = open(path, flags);
+    if (file < 0) {
+        fprintf(2, "sh: %s: cannot open\n", path);
+        return -1;
+    }
+    dup2(file, fd);
+    close(file);
+    return 0;
+}
+
+/* Runs the stages of a pipeline. Each stage is started with its stdin
+   and stdout redirected, which children inherit through `exec`. The
+   shell's own stdin and stdout are restored afterwards. */
+static void run(command cmds[], int n, int background) {
+    int pids[STAGES_MAX];
+    int in = -1; /* Read end of the previous pipe. */
+
+    for (int i = 0; i < n; i++) {
+        command* cmd = &cmds[i];
+        int saved_in = dup(0), saved_out = dup(1), fds[2];
+        int b = builtin(cmd->argv[0]);
+
+        pids[i] = -1;
+        if (in >= 0) {
+            dup2(in, 0);
+            close(in);
+            in = -1;
+        }
+        if (i + 1 < n) {
+            pipe(fds);
+            dup2(fds[1], 1);
+            close(fds[1]);
+            in = fds[0];
+        }
+
+        if (cmd->in && redirect(0, cmd->in, O_RDONLY) < 0) goto restore;
+        if (cmd->out && redirect(1, cmd->out, O_CREATE | O_WRONLY | O_TRUNC) < 0) goto restore;
+
+        if (b >= 0) {
+            // A builtin runs in the shell, so nothing would read the pipe yet.
+            if (i + 1 < n)
+                fprintf(2, "sh: %s: a builtin can't write to a pipe\n", cmd->argv[0]);
+            else
+                builtins[b].run(argc_of(cmd), cmd->argv);
+        } else if ((pids[i] = exec(cmd->argv[0], cmd->argv)) < 0) {
+            fprintf(2, "sh: %s: cannot run\n", cmd->argv[0]);
+        }
+
+    restore:
+        dup2(saved_in, 0);
+        dup2(saved_out, 1);
+        close(saved_in);
+        close(saved_out);
+    }
+
+    for (int i = 0; i < n; i++) {
+        if (pids[i] < 0) continue;
+        if (background && njobs < JOBS_MAX) {
+            jobs[njobs++] = pids[i];
+            printf("[%d]\n", pids[i]);
+        } else if (!background) {
+            wait(pids[i]);
+        }
+    }
+}
+
+void main(int argc, char* argv[]) {
+    static char line[LINE_MAX], buf[LINE_MAX * 2];
+    char* toks[TOKS_MAX];
+    command cmds[STAGES_MAX];
+    int n, background, interactive = argc < 2;
+
+    if (!interactive && redirect(0, argv[1], O_RDONLY) < 0) exit(1);
+
+    // Ctrl-C is for the command being waited for.
+    signal(SIGINT, SIG_IGN);
+
+    for (;;) {
+        if (interactive) printf("$ ");
+        if (read_line(line, sizeof line) < 0) break;
+
+        if ((n = tokenize(line, buf, toks, TOKS_MAX)) < 0) {
+            fprintf(2, "sh: too many words\n");
+            continue;
+        }
+        if ((n = parse(toks, n, cmds, &background)) > 0) run(cmds, n, background);
+    }
+}
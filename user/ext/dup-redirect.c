@@ -0,0 +1,30 @@
+/** Redirects stdout to a file with `dup2`, and restores it from a
+   duplicate made with `dup`. Duplicates share the file position. */
+
+#include "user.h"
+
+void main() {
+    char buf[16];
+    int fd, saved, copy;
+
+    assert((fd = open("/tmp/redirect", O_CREATE | O_RDWR)) > 2);
+    assert((saved = dup(1)) > 2);
+    assert(dup2(fd, 1) == 1);
+    printf("hidden");
+    assert(dup2(saved, 1) == 1);
+    assert(close(saved) == 0);
+    printf("stdout is back\n");
+
+    // Both descriptors refer to one description, whose position is at the end.
+    assert(tell(fd) == 6);
+    seek(fd, 0);
+    assert((copy = dup(fd)) > 2 && copy != fd);
+    assert(read(copy, buf, 3) == 3 && memcmp(buf, "hid", 3) == 0);
+    assert(tell(fd) == 3);
+    assert(close(fd) == 0);
+    assert(read(copy, buf, 3) == 3 && memcmp(buf, "den", 3) == 0);
+
+    assert(dup(fd) == -1, "closed descriptor");
+    assert(dup2(copy, copy) == copy);
+    close(copy);
+}
@@ -0,0 +1,90 @@
+/** Runs copies of this program with corrupted ELF headers, which exec
+   must refuse. An intact copy runs, and finds its BSS zeroed.
+
+   Usage: elf-bad [bss] */
+
+#include "user.h"
+
+#define COPY "/tmp/elf-bad"
+
+/* Offsets in Elf64_Ehdr. */
+#define E_TYPE 16
+#define E_MACHINE 18
+#define E_ENTRY 24
+#define E_PHOFF 32
+#define E_PHENTSIZE 54
+#define E_PHNUM 56
+
+/* Offsets in Elf64_Phdr. */
+#define P_TYPE 0
+#define P_OFFSET 8
+#define P_VADDR 16
+#define P_FILESZ 32
+#define P_MEMSZ 40
+
+#define PT_LOAD 1
+
+static char bss[3 * 4096 + 123];
+
+static char* image;
+static int size;
+
+/* The first loadable program header of IMG. */
+static char* first_load(char* img) {
+    char* ph = img + *(uint64*)(img + E_PHOFF);
+    for (int i = 0; i < *(uint16*)(img + E_PHNUM); i++, ph += *(uint16*)(img + E_PHENTSIZE))
+        if (*(uint*)(ph + P_TYPE) == PT_LOAD) return ph;
+    panic("no loadable segment");
+    return NULL;
+}
+
+/* Writes IMG to COPY, and returns the exit status of running it. */
+static int run(const char* img) {
+    int fd = open(COPY, O_CREATE | O_TRUNC | O_WRONLY);
+    assert(fd > 2);
+    assert(write(fd, img, size) == size);
+    close(fd);
+
+    const char* args[] = {COPY, "bss", NULL};
+    int pid = exec(args[0], args);
+    return pid == -1 ? -1 : wait(pid);
+}
+
+/* Runs a copy of the image with its word of WIDTH bytes at OFF set to
+   VALUE. */
+static int run_patched(int off, int width, uint64 value) {
+    char* img = malloc(size);
+    memcpy(img, image, size);
+    memcpy(img + off, &value, width);
+    int status = run(img);
+    free(img);
+    return status;
+}
+
+void main(int argc, char* argv[]) {
+    if (argc == 2 && strcmp(argv[1], "bss") == 0) {
+        for (int i = 0; i < sizeof bss; i++) assert(bss[i] == 0);
+        exit(0);
+    }
+
+    stat st;
+    int fd = open("elf-bad", O_RDONLY);
+    assert(fd > 2 && fstat(fd, &st) == 0);
+    size = st.size;
+    image = malloc(size);
+    assert(read(fd, image, size) == size);
+    close(fd);
+
+    assert(run(image) == 0, "intact copy");
+
+    int ph = first_load(image) - image;
+    uint64 memsz = *(uint64*)(first_load(image) + P_MEMSZ);
+    assert(run_patched(E_MACHINE, 2, 0x3e) == -1, "not RISC-V");
+    assert(run_patched(E_TYPE, 2, 1) == -1, "relocatable");
+    assert(run_patched(E_ENTRY, 8, 0) == -1, "entry not executable");
+    assert(run_patched(ph + P_FILESZ, 8, memsz + 1) == -1, "file size over memory size");
+    assert(run_patched(ph + P_OFFSET, 8, size) == -1, "content beyond the file");
+    assert(run_patched(ph + P_VADDR, 8, 0xffffffc080000000) == -1, "kernel address");
+
+    remove(COPY);
+}
@@ -0,0 +1,46 @@
+/** Child process run by exit-siblings test.
+
+   Usage: exit-siblings-child FD
+
+   Starts a thread blocked reading an empty pipe, one blocked on a futex
+   and another one joining it, writes a byte to FD to tell the parent
+   they are about to block, and exits with 7 while they are blocked. */
+
+#include "user.h"
+
+static int fds[2];
+static volatile int word = 0;
+static volatile int started = 0;
+
+static void read_pipe(void* arg) {
+    char c;
+    started++;
+    for (;;) read(fds[0], &c, 1);
+}
+
+static void wait_futex(void* arg) {
+    started++;
+    for (;;) futex_wait(&word, 0);
+}
+
+static void join(void* arg) {
+    started++;
+    thread_join((int)(uint64)arg);
+    for (;;)
+        ;
+}
+
+void main(int argc, char* argv[]) {
+    assert(argc == 2);
+    assert(pipe(fds) == 0);
+
+    assert(thread_create(read_pipe, NULL) > 0);
+    int waiter = thread_create(wait_futex, NULL);
+    assert(waiter > 0);
+    assert(thread_create(join, (void*)(uint64)waiter) > 0);
+    while (started < 3)
+        ;
+
+    assert(write(atoi(argv[1]), "", 1) == 1);
+    exit(7);
+}
@@ -0,0 +1,44 @@
+/** Exits a process while its other threads are blocked in the kernel,
+   on a pipe, on a futex and in a join. They are terminated, and the
+   parent's wait returns only once none of them is left. */
+
+#include "user.h"
+
+static char buf[4096];
+
+/* Whether a line of the thread list shows a thread of the child blocked. */
+static int child_blocked(const char* s) {
+    const char* name = "exit-siblings-child";
+    int len = strlen(name);
+
+    for (; *s; s++) {
+        if (memcmp(s, name, len) != 0) continue;
+        for (; *s && *s != '\n'; s++)
+            if (memcmp(s, "Blocked", 7) == 0) return 1;
+        if (!*s) break;
+    }
+    return 0;
+}
+
+void main() {
+    int fds[2], fd, n, total = 0;
+    char fd_str[5], c;
+
+    assert(pipe(fds) == 0);
+    itoa(fd_str, fds[1]);
+    const char* args[] = {"exit-siblings-child", fd_str, NULL};
+    int pid = exec(args[0], args);
+    assert(pid > 0);
+    close(fds[1]);
+    assert(read(fds[0], &c, 1) == 1, "child is ready");
+    close(fds[0]);
+
+    assert(wait(pid) == 7, "exit value of the first thread to exit");
+
+    assert((fd = open("/proc/threads", O_RDONLY)) > 2);
+    while (total < sizeof buf - 1 && (n = read(fd, buf + total, sizeof buf - 1 - total)) > 0)
+        total += n;
+    buf[total] = '\0';
+    close(fd);
+    assert(!child_blocked(buf), "no thread of the child is left blocked");
+}
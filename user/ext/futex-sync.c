@@ -0,0 +1,69 @@
+/** Synchronizes threads with the futex-based mutex and condition
+   variable in user/lib/thread.c. Threads increment a shared counter
+   under a mutex, and pass items through a bounded buffer guarded by
+   a condition variable. */
+
+#include "thread.h"
+#include "user.h"
+
+#define NTHREADS 4
+#define NINCS 2000
+#define NITEMS 100
+#define BUFSIZE 4
+
+static mutex lock = MUTEX_INIT;
+static int counter = 0;
+
+static condvar changed = CONDVAR_INIT;
+static int buf[BUFSIZE], head = 0, count = 0;
+static int consumed = 0;
+
+static void incr(void* arg) {
+    for (int i = 0; i < NINCS; i++) {
+        mutex_lock(&lock);
+        int c = counter;
+        counter = c + 1;
+        mutex_unlock(&lock);
+    }
+}
+
+static void produce(void* arg) {
+    for (int i = 1; i <= NITEMS; i++) {
+        mutex_lock(&lock);
+        while (count == BUFSIZE) condvar_wait(&changed, &lock);
+        buf[(head + count++) % BUFSIZE] = i;
+        condvar_broadcast(&changed);
+        mutex_unlock(&lock);
+    }
+}
+
+static void consume(void* arg) {
+    for (int i = 1; i <= NITEMS; i++) {
+        mutex_lock(&lock);
+        while (count == 0) condvar_wait(&changed, &lock);
+        assert(buf[head] == i, "items arrive in order");
+        head = (head + 1) % BUFSIZE, count--;
+        consumed++;
+        condvar_broadcast(&changed);
+        mutex_unlock(&lock);
+    }
+}
+
+void main() {
+    int tids[NTHREADS];
+    int word = 1;
+
+    assert(futex_wait(&word, 0) == -1, "value changed already");
+    assert(futex_wake(&word, 1) == 0, "nobody is waiting");
+    assert(futex_wait((int*)((char*)&word + 1), 1) == -1, "misaligned");
+
+    for (int i = 0; i < NTHREADS; i++) assert((tids[i] = thread_create(incr, NULL)) > 0);
+    for (int i = 0; i < NTHREADS; i++) assert(thread_join(tids[i]) == 0);
+    assert(counter == NTHREADS * NINCS);
+
+    assert((tids[0] = thread_create(consume, NULL)) > 0);
+    assert((tids[1] = thread_create(produce, NULL)) > 0);
+    assert(thread_join(tids[1]) == 0);
+    assert(thread_join(tids[0]) == 0);
+    assert(consumed == NITEMS && count == 0);
+}
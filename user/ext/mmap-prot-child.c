@@ -0,0 +1,39 @@
+/** Child process run by mmap-prot test.
+
+   Usage: mmap-prot-child write-ro|read-guard|exec-nx|exec-x
+
+   Maps a page and accesses it against its protection, which should
+   kill the process, except in exec-x, which runs code it wrote after
+   making the page executable. */
+
+#include "user.h"
+
+#define PGSIZE 4096
+#define BASE ((char*)0x40000000)
+
+/* `ret` in RISC-V. */
+#define RET 0x00008067
+
+void main(int argc, char* argv[]) {
+    volatile char* p = BASE;
+
+    assert(argc == 2);
+    const char* mode = argv[1];
+    assert(mmap_anon(BASE, PGSIZE, PROT_READ | PROT_WRITE) == BASE);
+    *(volatile uint*)p = RET;
+
+    if (strcmp(mode, "write-ro") == 0) {
+        assert(mprotect(BASE, PGSIZE, PROT_READ) == 0);
+        p[0] = 1;
+    } else if (strcmp(mode, "read-guard") == 0) {
+        assert(mprotect(BASE, PGSIZE, PROT_NONE) == 0);
+        (void)p[0];
+    } else {
+        int prot = strcmp(mode, "exec-x") == 0 ? PROT_READ | PROT_EXEC : PROT_READ;
+        assert(mprotect(BASE, PGSIZE, prot) == 0);
+        asm volatile("fence.i");
+        ((void (*)(void))BASE)();
+        exit(0);
+    }
+    panic("survived a bad access");
+}
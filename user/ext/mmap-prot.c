@@ -0,0 +1,63 @@
+/** Maps anonymous pages and changes their protections. Zero pages are
+   mapped with the asked permissions, bad or too large ranges are
+   refused, and the kernel can't write to a read-only page nor read a
+   guard page. Faulting accesses are run in mmap-prot-child, which must
+   die of them. */
+
+#include "user.h"
+
+#define PGSIZE 4096
+#define BASE ((char*)0x40000000)
+
+static int all_zero(const char* p, int n) {
+    for (int i = 0; i < n; i++)
+        if (p[i] != 0) return 0;
+    return 1;
+}
+
+/* Runs mmap-prot-child in MODE, and returns its exit status. */
+static int run(const char* mode) {
+    const char* args[] = {"mmap-prot-child", mode, NULL};
+    int pid = exec(args[0], args);
+    assert(pid > 0);
+    return wait(pid);
+}
+
+void main() {
+    char* p = BASE;
+
+    assert(mmap_anon(p, 2 * PGSIZE, PROT_READ | PROT_WRITE) == p);
+    assert(all_zero(p, 2 * PGSIZE));
+    memset(p, 0x5a, 2 * PGSIZE);
+
+    assert(mmap_anon(p + PGSIZE, PGSIZE, PROT_READ) == (void*)-1, "overlap");
+    assert(mmap_anon(p + 1, PGSIZE, PROT_READ) == (void*)-1, "misaligned");
+    assert(mmap_anon(p + 2 * PGSIZE, 0, PROT_READ) == (void*)-1, "empty");
+    assert(mmap_anon(NULL, PGSIZE, PROT_READ) == (void*)-1, "NULL");
+    assert(mmap_anon(p + 2 * PGSIZE, PGSIZE, 8) == (void*)-1, "unknown prot");
+    assert(mmap_anon(p + 2 * PGSIZE, 1024 * PGSIZE, PROT_READ) == (void*)-1, "more than memory");
+    char* brk = (char*)ROUND_UP(sbrk(0), PGSIZE);
+    assert(mmap_anon(brk, PGSIZE, PROT_READ) == brk);
+    assert(sbrk(PGSIZE) == (void*)-1, "heap can't grow into a mapping");
+
+    assert(mprotect(p, 2 * PGSIZE, PROT_READ) == 0);
+    assert(p[PGSIZE] == 0x5a, "contents are kept");
+    int fd = open("/dev/zero", O_RDONLY);
+    assert(read(fd, p, 1) == -1, "the kernel can't write a read-only page");
+    close(fd);
+    assert(mprotect(p, PGSIZE, PROT_NONE) == 0);
+    fd = open("/dev/null", O_WRONLY);
+    assert(write(fd, p, 1) == -1, "the kernel can't read a guard page");
+    close(fd);
+    assert(mprotect(p, 2 * PGSIZE, PROT_READ | PROT_WRITE) == 0);
+    p[0] = 1;
+
+    assert(mprotect(p + 2 * PGSIZE, PGSIZE, PROT_READ) == -1, "not mapped");
+    assert(mprotect(p, 3 * PGSIZE, PROT_NONE) == -1, "partly mapped");
+    assert(p[0] == 1, "nothing changed");
+
+    assert(run("write-ro") == -1);
+    assert(run("read-guard") == -1);
+    assert(run("exec-nx") == -1);
+    assert(run("exec-x") == 0);
+}
@@ -0,0 +1,34 @@
+/** Sends a message from a child process through a pipe. The parent
+   reads until EOF, which arrives once the child has exited and the
+   parent has closed its own copy of the write end. Writing after all
+   readers are closed must fail. */
+
+#include "user.h"
+
+void main() {
+    int fds[2];
+    char buf[64], fd_str[5];
+    int n, total = 0;
+    const char* msg = "through the pipe";
+
+    assert(pipe(fds) == 0);
+    assert(fds[0] > 2 && fds[1] > 2 && fds[0] != fds[1]);
+    assert(write(fds[0], "x", 1) == -1, "read end is not writable");
+    assert(read(fds[1], buf, 1) == -1, "write end is not readable");
+
+    itoa(fd_str, fds[1]);
+    const char* args[] = {"pipe-echo", fd_str, msg, NULL};
+    int pid = exec(args[0], args);
+    assert(pid > 0);
+    assert(close(fds[1]) == 0);
+
+    while ((n = read(fds[0], buf + total, sizeof buf - total)) > 0) total += n;
+    assert(n == 0, "EOF after the writer exits");
+    assert(total == strlen(msg) && memcmp(buf, msg, total) == 0);
+    assert(wait(pid) == NORMAL_EXIT);
+    close(fds[0]);
+
+    assert(pipe(fds) == 0);
+    assert(close(fds[0]) == 0);
+    assert(write(fds[1], msg, 4) == -1, "no reader left");
+}
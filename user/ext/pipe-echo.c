@@ -0,0 +1,13 @@
+/** Child process run by pipe-basic test.
+
+   Writes its second argument to the file descriptor passed as the
+   first, which is the write end of a pipe inherited through `exec`. */
+
+#include "user.h"
+
+void main(int argc, char* argv[]) {
+    assert(argc == 3);
+    int fd = atoi(argv[1]);
+    int len = strlen(argv[2]);
+    assert(write(fd, argv[2], len) == len);
+}
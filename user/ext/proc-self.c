@@ -0,0 +1,44 @@
+/** Reads the current process's mappings, the thread list and the thread
+   statistics from procfs, which is read-only. */
+
+#include "user.h"
+
+static int read_file(const char* path, char* buf, int size) {
+    int fd, n, total = 0;
+
+    assert((fd = open(path, O_RDONLY)) > 2, "open \"%s\"", path);
+    while (total < size - 1 && (n = read(fd, buf + total, size - 1 - total)) > 0) total += n;
+    buf[total] = '\0';
+    close(fd);
+    return total;
+}
+
+static int contains(const char* s, const char* pattern) {
+    int len = strlen(pattern);
+    for (; *s; s++)
+        if (memcmp(s, pattern, len) == 0) return 1;
+    return 0;
+}
+
+void main() {
+    char buf[2048];
+    int fd;
+
+    assert(read_file("/proc/self/maps", buf, sizeof buf) > 0, "maps of self");
+    printf("%s", buf);
+    assert(contains(buf, "r-x"), "code is mapped executable");
+    assert(contains(buf, "rw-"), "stack is mapped writable");
+
+    assert(read_file("/proc/threads", buf, sizeof buf) > 0, "thread list");
+    printf("%s", buf);
+    assert(contains(buf, "Idle"), "idle thread is listed");
+
+    assert(read_file("/proc/stat", buf, sizeof buf) > 0, "thread statistics");
+    printf("%s", buf);
+    assert(contains(buf, "IVCSW"), "switches are counted");
+
+    assert((fd = open("/proc/meminfo", O_RDWR)) > 2, "open \"/proc/meminfo\"");
+    assert(write(fd, "x", 1) == -1, "procfs is read-only");
+    close(fd);
+    assert(open("/proc/nothing", O_CREATE) == -1, "create in procfs");
+}
@@ -0,0 +1,74 @@
+/** Grows and shrinks the heap with sbrk. New heap pages read as zero,
+   also after a shrink and a regrowth, and the kernel can touch them
+   first. Then checks malloc, which is built on sbrk.
+
+   Run with `hog`, it touches a heap larger than memory, which must
+   kill it. */
+
+#include "user.h"
+
+#define PGSIZE 4096
+#define NBLOCKS 64
+#define HOG_PAGES 1024
+
+static int all_zero(const char* p, int n) {
+    for (int i = 0; i < n; i++)
+        if (p[i] != 0) return 0;
+    return 1;
+}
+
+void main(int argc, char* argv[]) {
+    char* start = sbrk(0);
+
+    if (argc == 2 && strcmp(argv[1], "hog") == 0) {
+        assert(sbrk(HOG_PAGES * PGSIZE) == start);
+        for (int i = 0; i < HOG_PAGES; i++) start[i * PGSIZE] = 1;
+        panic("touched more pages than memory");
+    }
+
+    assert(sbrk(3 * PGSIZE) == start);
+    assert(sbrk(0) == start + 3 * PGSIZE);
+    assert(all_zero(start, 3 * PGSIZE));
+    memset(start, 0x5a, 3 * PGSIZE);
+
+    assert(sbrk(-3 * PGSIZE) == start + 3 * PGSIZE);
+    assert(sbrk(0) == start);
+    assert(sbrk(-1) == (void*)-1, "below the start of the heap");
+    assert(sbrk(1L << 40) == (void*)-1, "too large");
+
+    assert(sbrk(3 * PGSIZE) == start);
+    assert(all_zero(start, 3 * PGSIZE), "zero after regrowth");
+
+    // The kernel faults in untouched pages when copying to and from them.
+    char* fresh = sbrk(2 * PGSIZE);
+    int fd = open("/dev/null", O_WRONLY);
+    assert(write(fd, fresh + PGSIZE, 100) == 100);
+    close(fd);
+    memset(fresh, 1, PGSIZE);
+    fd = open("/dev/zero", O_RDONLY);
+    assert(read(fd, fresh, PGSIZE) == PGSIZE);
+    assert(all_zero(fresh, PGSIZE));
+    close(fd);
+
+    char* blocks[NBLOCKS];
+    for (int i = 0; i < NBLOCKS; i++) {
+        blocks[i] = malloc(i * 37 + 1);
+        assert(blocks[i] != NULL);
+        memset(blocks[i], i, i * 37 + 1);
+    }
+    for (int i = 0; i < NBLOCKS; i += 2) free(blocks[i]);
+    for (int i = 0; i < NBLOCKS; i += 2) {
+        blocks[i] = malloc(i * 37 + 1);
+        memset(blocks[i], i, i * 37 + 1);
+    }
+    for (int i = 0; i < NBLOCKS; i++) {
+        for (int j = 0; j < i * 37 + 1; j++) assert(blocks[i][j] == (char)i, "blocks don't overlap");
+        free(blocks[i]);
+    }
+    assert(malloc(1L << 40) == NULL);
+
+    const char* args[] = {"sbrk-heap", "hog", NULL};
+    int pid = exec(args[0], args);
+    assert(pid > 0);
+    assert(wait(pid) == -1, "running out of memory kills the process");
+}
@@ -0,0 +1,63 @@
+/** Runs scripts through exec. A `#!` line names the interpreter and
+   maybe one argument, which go before the script path in argv. Scripts
+   may be interpreted by scripts, but not endlessly, and `sh` runs the
+   commands in a script.
+
+   This program is also the interpreter. It exits with 100 times its
+   argc plus its first argument as a number. */
+
+#include "user.h"
+
+/* Writes TEXT to the file at PATH. */
+static void put(const char* path, const char* text) {
+    int fd = open(path, O_CREATE | O_TRUNC | O_WRONLY);
+    assert(fd > 2);
+    assert(write(fd, text, strlen(text)) == strlen(text));
+    close(fd);
+}
+
+/* Runs the script at PATH with argument x, and returns its exit status. */
+static int run(const char* path) {
+    const char* args[] = {path, "x", NULL};
+    int pid = exec(path, args);
+    return pid == -1 ? -1 : wait(pid);
+}
+
+void main(int argc, char* argv[]) {
+    if (strcmp(argv[0], "/script-run") == 0) {
+        assert(strcmp(argv[argc - 1], "x") == 0);
+        exit(argc * 100 + atoi(argv[1]));
+    }
+
+    put("/tmp/args", "#!/script-run 42\n");
+    assert(run("/tmp/args") == 442, "argv is `/script-run 42 /tmp/args x`");
+    put("/tmp/args", "#!/script-run\n");
+    assert(run("/tmp/args") == 300, "argv is `/script-run /tmp/args x`");
+    put("/tmp/args", "#! /script-run  4 2  \n");
+    assert(run("/tmp/args") == 404, "argv is `/script-run '4 2' /tmp/args x`");
+
+    put("/tmp/args", "#!/script-run 42\n");
+    put("/tmp/nested", "#!/tmp/args\n");
+    assert(run("/tmp/nested") == 542, "argv is `/script-run 42 /tmp/args /tmp/nested x`");
+
+    put("/tmp/loop", "#!/tmp/loop\n");
+    assert(run("/tmp/loop") == -1, "too deep");
+    put("/tmp/none", "#!/no-such-file\n");
+    assert(run("/tmp/none") == -1, "no interpreter");
+    put("/tmp/empty", "#!  \n");
+    assert(run("/tmp/empty") == -1, "interpreter not named");
+
+    put("/tmp/sh", "#!/sh\n# A comment.\nargs-none > /tmp/out # Another.\nexit 7\n");
+    assert(run("/tmp/sh") == 7, "sh runs the script");
+    int fd = open("/tmp/out", O_RDONLY);
+    assert(fd > 2, "sh ran args-none");
+    close(fd);
+
+    remove("/tmp/args");
+    remove("/tmp/nested");
+    remove("/tmp/loop");
+    remove("/tmp/none");
+    remove("/tmp/empty");
+    remove("/tmp/sh");
+    remove("/tmp/out");
+}
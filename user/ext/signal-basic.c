@@ -0,0 +1,57 @@
+/** Sends signals to child processes. A caught SIGTERM runs the
+   child's handler and returns to where it was interrupted, while
+   SIGTERM by default and SIGKILL always terminate the child. Each
+   exiting child sends SIGCHLD to this process. */
+
+#include "user.h"
+
+static volatile int chld = 0;
+
+static void on_chld(int sig) {
+    assert(sig == SIGCHLD);
+    chld++;
+}
+
+/* Runs signal-child in MODE, and waits until it is ready for signals. */
+static int spawn(const char* mode) {
+    int fds[2];
+    char fd_str[5], c;
+
+    assert(pipe(fds) == 0);
+    itoa(fd_str, fds[1]);
+    const char* args[] = {"signal-child", mode, fd_str, NULL};
+    int pid = exec(args[0], args);
+    assert(pid > 0);
+    close(fds[1]);
+    assert(read(fds[0], &c, 1) == 1, "child is ready");
+    close(fds[0]);
+    return pid;
+}
+
+void main() {
+    int pid;
+
+    assert(signal(SIGKILL, SIG_IGN) == SIG_ERR, "SIGKILL can't be caught");
+    assert(signal(3, SIG_IGN) == SIG_ERR, "no such signal");
+    assert(kill(12345, SIGTERM) == -1, "no such process");
+
+    assert(signal(SIGCHLD, on_chld) == SIG_DFL);
+    assert(signal(SIGCHLD, on_chld) == on_chld);
+
+    pid = spawn("catch");
+    assert(kill(pid, SIGTERM) == 0);
+    assert(wait(pid) == 42, "handler ran and returned");
+    assert(chld == 1);
+
+    pid = spawn("catch");
+    assert(kill(pid, SIGKILL) == 0);
+    assert(wait(pid) == -1, "killed in spite of a handler");
+    assert(chld == 2);
+
+    pid = spawn("loop");
+    assert(kill(pid, SIGTERM) == 0);
+    assert(wait(pid) == -1, "terminated by default");
+    assert(chld == 3);
+
+    assert(kill(pid, SIGTERM) == -1, "already exited");
+}
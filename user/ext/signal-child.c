@@ -0,0 +1,27 @@
+/** Child process run by signal-basic test.
+
+   Usage: signal-child catch|loop FD
+
+   Optionally catches SIGTERM, writes a byte to FD to tell the parent
+   it's ready, and spins. A caught SIGTERM makes it exit with 42. */
+
+#include "user.h"
+
+static volatile int caught = 0;
+
+static void on_term(int sig) {
+    assert(sig == SIGTERM);
+    caught = 1;
+}
+
+void main(int argc, char* argv[]) {
+    assert(argc == 3);
+    if (strcmp(argv[1], "catch") == 0) {
+        assert(signal(SIGTERM, on_term) == SIG_DFL);
+    }
+
+    assert(write(atoi(argv[2]), "", 1) == 1);
+    while (!caught)
+        ;
+    exit(42);
+}
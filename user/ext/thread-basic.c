@@ -0,0 +1,64 @@
+/** Runs threads sharing the memory of the process. Threads live at the
+   same time get separate stacks, a thread can be joined only once,
+   exited threads count against the thread limit until joined, and
+   exiting the process terminates threads still running. */
+
+#include "user.h"
+
+#define NTHREADS 4
+
+static volatile int go = 0;
+static int squares[NTHREADS];
+static uint64 stacks[NTHREADS];
+
+/* More than a process may have. */
+#define MAX_UNJOINED 64
+static int unjoined[MAX_UNJOINED];
+
+static void square(void* arg) {
+    int i = (int)(uint64)arg;
+    int local = i * i;
+
+    // Keep every thread alive until all of them are created.
+    while (!go)
+        ;
+    stacks[i] = (uint64)&local;
+    squares[i] = local;
+}
+
+static void nop(void* arg) {}
+
+static void spin(void* arg) {
+    for (;;)
+        ;
+}
+
+void main() {
+    int tids[NTHREADS];
+
+    for (int i = 0; i < NTHREADS; i++) {
+        tids[i] = thread_create(square, (void*)(uint64)i);
+        assert(tids[i] > 0);
+    }
+    go = 1;
+    for (int i = 0; i < NTHREADS; i++) assert(thread_join(tids[i]) == 0);
+
+    for (int i = 0; i < NTHREADS; i++) {
+        assert(squares[i] == i * i);
+        for (int j = 0; j < i; j++) assert(stacks[i] != stacks[j], "separate stacks");
+    }
+
+    assert(thread_join(tids[0]) == -1, "joined already");
+    assert(thread_join(12345) == -1, "not a thread of this process");
+
+    // Exited threads count against the limit until they are joined.
+    int n = 0;
+    while (n < MAX_UNJOINED && (unjoined[n] = thread_create(nop, NULL)) > 0) n++;
+    assert(n > 0 && n < MAX_UNJOINED, "thread creation is capped");
+    for (int i = 0; i < n; i++) assert(thread_join(unjoined[i]) == 0);
+    assert((tids[0] = thread_create(nop, NULL)) > 0, "joining frees the threads");
+    assert(thread_join(tids[0]) == 0);
+
+    // Returning from main exits the process, and the spinning thread.
+    assert(thread_create(spin, NULL) > 0);
+}
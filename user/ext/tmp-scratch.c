@@ -0,0 +1,24 @@
+/** Creates a scratch file on tmpfs, reads it back, then removes it. */
+
+#include "user.h"
+
+void main() {
+    const char* path = "/tmp/scratch";
+    const char* data = "scratch data";
+    int size = strlen(data);
+    char buf[32];
+    stat s;
+    int fd;
+
+    assert((fd = open(path, O_CREATE | O_RDWR)) > 2, "create \"%s\"", path);
+    assert(write(fd, data, size) == size, "write to \"%s\"", path);
+    assert(fstat(fd, &s) == 0 && s.size == size, "size of \"%s\"", path);
+
+    seek(fd, 0);
+    assert(read(fd, buf, sizeof buf) == size, "read back \"%s\"", path);
+    assert(memcmp(buf, data, size) == 0, "contents of \"%s\"", path);
+    close(fd);
+
+    assert(remove(path) == 0, "remove \"%s\"", path);
+    assert(open(path, O_RDONLY) == -1, "open removed \"%s\"", path);
+}
@@ -6,10 +6,18 @@
 #define T_DIR 1     // Directory
 #define T_FILE 2    // File
 #define T_DEVICE 3  // Device
+#define T_PIPE 4    // Pipe
 
 typedef struct {
     uint ino;     // Inode number
     uint64 size;  // Size of file in bytes
 } stat;
 
+#define DIRSIZ 28  // Max length of a name in dirent, including the NUL
+
+typedef struct {
+    char name[DIRSIZ];  // Name, truncated if too long
+    uint type;          // One of T_*
+} dirent;
+
 #endif
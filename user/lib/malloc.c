@@ -0,0 +1,97 @@
+/* A first-fit allocator with a circular free list, after the one in
+   K&R. Memory comes from sbrk, and is never given back. A mutex makes
+   it safe to call from several threads. */
+
+#include "thread.h"
+#include "user.h"
+
+/* Header of a block, also the unit of allocation. */
+typedef union header {
+    struct {
+        union header* next; /* Next free block. */
+        size_t units;       /* Size of this block, in units. */
+    } s;
+    long double align;
+} header;
+
+/* Least number of units to ask sbrk for. */
+#define SBRK_UNITS 512
+
+static header base;
+static header* freep = NULL;
+static mutex lock = MUTEX_INIT;
+
+/* Returns block BP to the free list, merging it with its neighbours. */
+static void release(header* bp) {
+    header* p;
+
+    for (p = freep; !(bp > p && bp < p->s.next); p = p->s.next) {
+        if (p >= p->s.next && (bp > p || bp < p->s.next)) break;
+    }
+
+    if (bp + bp->s.units == p->s.next) {
+        bp->s.units += p->s.next->s.units;
+        bp->s.next = p->s.next->s.next;
+    } else {
+        bp->s.next = p->s.next;
+    }
+    if (p + p->s.units == bp) {
+        p->s.units += bp->s.units;
+        p->s.next = bp->s.next;
+    } else {
+        p->s.next = bp;
+    }
+    freep = p;
+}
+
+/* Grows the heap by at least UNITS. Returns NULL if out of memory. */
+static header* morecore(size_t units) {
+    if (units < SBRK_UNITS) units = SBRK_UNITS;
+
+    char* p = sbrk(units * sizeof(header));
+    if (p == (char*)-1) return NULL;
+
+    header* hp = (header*)p;
+    hp->s.units = units;
+    release(hp);
+    return freep;
+}
+
+void* malloc(size_t nbytes) {
+    header *p, *prevp;
+    size_t units = (nbytes + sizeof(header) - 1) / sizeof(header) + 1;
+    void* ret = NULL;
+
+    mutex_lock(&lock);
+    if ((prevp = freep) == NULL) {
+        base.s.next = freep = prevp = &base;
+        base.s.units = 0;
+    }
+
+    for (p = prevp->s.next;; prevp = p, p = p->s.next) {
+        if (p->s.units >= units) {
+            if (p->s.units == units) {
+                prevp->s.next = p->s.next;
+            } else {
+                /* Allocate the tail end. */
+                p->s.units -= units;
+                p += p->s.units;
+                p->s.units = units;
+            }
+            freep = prevp;
+            ret = p + 1;
+            break;
+        }
+        if (p == freep && (p = morecore(units)) == NULL) break;
+    }
+    mutex_unlock(&lock);
+    return ret;
+}
+
+void free(void* ap) {
+    if (ap == NULL) return;
+
+    mutex_lock(&lock);
+    release((header*)ap - 1);
+    mutex_unlock(&lock);
+}
@@ -18,3 +18,21 @@
 /* Project 4 only. */
 #define SYS_CHDIR 15 /**< Change the current directory. */
 #define SYS_MKDIR 16 /**< Create a directory. */
+
+/* Extensions. */
+#define SYS_PIPE 17        /**< Create a pipe. */
+#define SYS_DUP 18         /**< Duplicate a file descriptor. */
+#define SYS_DUP2 19        /**< Duplicate a file descriptor to a given one. */
+#define SYS_READDIR 20     /**< List entries of a directory. */
+#define SYS_CONSMODE 21    /**< Set the console input mode. */
+#define SYS_KILL 22        /**< Send a signal to a process. */
+#define SYS_SIGACTION 23   /**< Set the handler of a signal. */
+#define SYS_SIGRETURN 24   /**< Return from a signal handler. */
+#define SYS_CLONE 25       /**< Start a thread in this process. */
+#define SYS_THREAD_JOIN 26 /**< Wait for a thread to exit. */
+#define SYS_THREAD_EXIT 27 /**< Terminate this thread. */
+#define SYS_FUTEX_WAIT 28  /**< Block on a futex. */
+#define SYS_FUTEX_WAKE 29  /**< Wake up threads blocked on a futex. */
+#define SYS_SBRK 30        /**< Move the program break. */
+#define SYS_MMAP_ANON 31   /**< Map zero pages. */
+#define SYS_MPROTECT 32    /**< Change page permissions. */
@@ -0,0 +1,51 @@
+/* Mutexes and condition variables for user threads, blocking with
+   futex_wait and futex_wake rather than spinning.
+
+   The mutex follows "Futexes Are Tricky" by Ulrich Drepper: unlocking
+   only makes a syscall if some thread may be blocked. */
+
+#include "thread.h"
+
+#include "user.h"
+
+void mutex_init(mutex* m) { m->state = 0; }
+
+void mutex_lock(mutex* m) {
+    int c = __sync_val_compare_and_swap(&m->state, 0, 1);
+    if (c == 0) return;
+
+    /* Mark the mutex contended, and block until it's unlocked. */
+    if (c != 2) c = __atomic_exchange_n(&m->state, 2, __ATOMIC_ACQUIRE);
+    while (c != 0) {
+        futex_wait(&m->state, 2);
+        c = __atomic_exchange_n(&m->state, 2, __ATOMIC_ACQUIRE);
+    }
+}
+
+void mutex_unlock(mutex* m) {
+    if (__atomic_fetch_sub(&m->state, 1, __ATOMIC_RELEASE) != 1) {
+        __atomic_store_n(&m->state, 0, __ATOMIC_RELEASE);
+        futex_wake(&m->state, 1);
+    }
+}
+
+void condvar_init(condvar* cv) { cv->seq = 0; }
+
+/* Blocks until signaled. A signal between unlocking M and blocking
+   changes SEQ, so futex_wait returns at once rather than missing it. */
+void condvar_wait(condvar* cv, mutex* m) {
+    int seq = cv->seq;
+    mutex_unlock(m);
+    futex_wait(&cv->seq, seq);
+    mutex_lock(m);
+}
+
+void condvar_signal(condvar* cv) {
+    __atomic_fetch_add(&cv->seq, 1, __ATOMIC_RELEASE);
+    futex_wake(&cv->seq, 1);
+}
+
+void condvar_broadcast(condvar* cv) {
+    __atomic_fetch_add(&cv->seq, 1, __ATOMIC_RELEASE);
+    futex_wake(&cv->seq, 0x7fffffff);
+}
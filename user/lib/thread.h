@@ -0,0 +1,27 @@
+#ifndef __LIB_THREAD_H
+#define __LIB_THREAD_H
+
+/* Mutex. 0 if unlocked, 1 if locked, 2 if locked and there may be
+   waiters. */
+typedef struct {
+    volatile int state;
+} mutex;
+
+/* Condition variable. Bumped by every signal. */
+typedef struct {
+    volatile int seq;
+} condvar;
+
+#define MUTEX_INIT {0}
+#define CONDVAR_INIT {0}
+
+void mutex_init(mutex*);
+void mutex_lock(mutex*);
+void mutex_unlock(mutex*);
+
+void condvar_init(condvar*);
+void condvar_wait(condvar*, mutex*);
+void condvar_signal(condvar*);
+void condvar_broadcast(condvar*);
+
+#endif
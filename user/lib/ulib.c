@@ -3,9 +3,9 @@
 #include "user.h"
 
 // wrapper so that it's OK if main() does not call exit().
-void _main() {
-    extern void main();
-    main();
+void _main(int argc, char* argv[]) {
+    extern void main(int, char**);
+    main(argc, argv);
     exit(NORMAL_EXIT);
 }
 
@@ -182,3 +182,9 @@ inline uint64 r_sp() {
     asm volatile("mv %0, sp" : "=r"(x));
     return x;
 }
+
+// Handlers return to sigreturn, which restores the interrupted registers.
+sighandler_t signal(int sig, sighandler_t handler) { return sigaction(sig, handler, sigreturn); }
+
+// Threads return to thread_exit.
+int thread_create(void (*fn)(void*), void* arg) { return clone(fn, arg, thread_exit); }
@@ -13,6 +13,27 @@
 #define PANIC_EXIT 12345
 #define NORMAL_EXIT 0
 
+// console input modes, see consmode
+#define CONS_COOKED 0
+#define CONS_RAW 1
+
+// page permissions, see mmap_anon and mprotect
+#define PROT_NONE 0
+#define PROT_READ 1
+#define PROT_WRITE 2
+#define PROT_EXEC 4
+
+// signals, see signal
+#define SIGINT 2
+#define SIGKILL 9
+#define SIGTERM 15
+#define SIGCHLD 17
+
+typedef void (*sighandler_t)(int);
+#define SIG_DFL ((sighandler_t)0)
+#define SIG_IGN ((sighandler_t)1)
+#define SIG_ERR ((sighandler_t)-1)
+
 #define panic(fmt, args...)                                                       \
     do {                                                                          \
         fprintf(2, "panicked at '" fmt "', %s:%d\n", ##args, __FILE__, __LINE__); \
@@ -42,6 +63,22 @@ int mmap(int fd, void* addr);
 void munmap(int mapid);
 int chdir(const char* dir);
 int mkdir(const char* dir);
+int pipe(int fds[2]);
+int dup(int fd);
+int dup2(int oldfd, int newfd);
+int readdir(const char* dir, dirent* ents, int n);
+int consmode(int mode);
+int kill(int pid, int sig);
+sighandler_t sigaction(int sig, sighandler_t handler, void (*restorer)(void));
+void sigreturn(void);
+int clone(void (*fn)(void*), void* arg, void (*ret)(void));
+int thread_join(int tid);
+void thread_exit(void);
+int futex_wait(volatile int* addr, int expected);
+int futex_wake(volatile int* addr, int n);
+void* sbrk(long increment);
+void* mmap_anon(void* addr, size_t len, int prot);
+int mprotect(void* addr, size_t len, int prot);
 
 // ulib.c
 void fprintf(int fd, const char* fmt, ...);
@@ -57,5 +94,11 @@ void* memcpy(void*, const void*, size_t);
 void check_file(const char*, const void* buf, size_t);
 void check_file_handle(int fd, const char* file_name, const void* buf_, size_t size);
 uint64 r_sp();
+sighandler_t signal(int sig, sighandler_t handler);
+int thread_create(void (*fn)(void*), void* arg);
+
+// malloc.c
+void* malloc(size_t);
+void free(void*);
 
 #endif
@@ -30,3 +30,19 @@ entry("mmap");
 entry("munmap");
 entry("chdir");
 entry("mkdir");
+entry("pipe");
+entry("dup");
+entry("dup2");
+entry("readdir");
+entry("consmode");
+entry("kill");
+entry("sigaction");
+entry("sigreturn");
+entry("clone");
+entry("thread_join");
+entry("thread_exit");
+entry("futex_wait");
+entry("futex_wake");
+entry("sbrk");
+entry("mmap_anon");
+entry("mprotect");
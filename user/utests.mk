@@ -1,6 +1,6 @@
 INC_DIR := user/lib
 BUILD_DIR := build
-SRC_DIRS := user/userprogs user/vm
+SRC_DIRS := user/userprogs user/vm user/ext user/bin
 
 TOOLPREFIX := riscv64-unknown-elf-
 CC := $(TOOLPREFIX)gcc